@@ -6,18 +6,30 @@ use std::{
         AddAssign,
         Div,
     },
-    collections::HashMap,
+    any::Any,
+    collections::{
+        HashMap,
+        hash_map::Entry,
+    },
     rc::Rc,
-    cell::RefCell,
+    cell::{
+        Cell,
+        RefCell,
+    },
 };
 use crate::{
     EventGraph,
     core::{
         Id,
         NULL_ID,
+        new_id,
+    },
+    prim::{
+        WeakPrim,
+        WeakHistPrim,
     },
-    prim::WeakHistPrim,
     ProcessingContext,
+    Prim,
     HistPrim,
 };
 
@@ -38,23 +50,104 @@ impl EaseUnit for f64 {
     }
 }
 
+/// An easing function, see `EaseExt`.
+pub type EaseFn<S> = Box<dyn Fn(S) -> S>;
+
+/// A value that can be animated, like `Prim` and `HistPrim`.  Implement this to
+/// animate other values.
+pub trait Animatable<T>: Sized + 'static {
+    type Weak: 'static;
+
+    /// A stable id for the value.  This is used to replace existing animations for the
+    /// value.
+    fn animation_id(&self) -> Id;
+
+    /// Get a copy of the current value.
+    fn animation_get(&self) -> T;
+
+    /// Set the value, like `set`.
+    fn animation_set(&self, pc: &mut ProcessingContext, value: T);
+
+    fn animation_weak(&self) -> Self::Weak;
+
+    fn animation_upgrade(weak: &Self::Weak) -> Option<Self>;
+}
+
+impl<T: Clone + 'static> Animatable<T> for Prim<T> {
+    type Weak = WeakPrim<T>;
+
+    fn animation_id(&self) -> Id {
+        return self.0.id;
+    }
+
+    fn animation_get(&self) -> T {
+        return self.borrow().clone();
+    }
+
+    fn animation_set(&self, pc: &mut ProcessingContext, value: T) {
+        self.set(pc, value);
+    }
+
+    fn animation_weak(&self) -> Self::Weak {
+        return self.weak();
+    }
+
+    fn animation_upgrade(weak: &Self::Weak) -> Option<Self> {
+        return weak.upgrade();
+    }
+}
+
+impl<T: PartialEq + Clone + 'static> Animatable<T> for HistPrim<T> {
+    type Weak = WeakHistPrim<T>;
+
+    fn animation_id(&self) -> Id {
+        return self.0.id;
+    }
+
+    fn animation_get(&self) -> T {
+        return self.get();
+    }
+
+    fn animation_set(&self, pc: &mut ProcessingContext, value: T) {
+        self.set(pc, value);
+    }
+
+    fn animation_weak(&self) -> Self::Weak {
+        return self.weak();
+    }
+
+    fn animation_upgrade(weak: &Self::Weak) -> Option<Self> {
+        return weak.upgrade();
+    }
+}
+
 /// Implement this to create custom antimations, including animations that run
 /// forever.
-pub trait HistPrimAnimation {
-    /// After doing whatever calculations, call `set` on the primitive being animated
-    /// like normal.  Return `true` until the animation is done.
+pub trait Animation {
+    /// After doing whatever calculations, call `set` on the value being animated like
+    /// normal.  Return `true` until the animation is done.
     fn update(&mut self, pc: &mut ProcessingContext, delta_ms: f64) -> bool;
 
-    /// The id of the primitive being animated. This is isued to replace existing
-    /// animations for the primitive.
+    /// The id of the value being animated (see `Animatable::animation_id`). This is
+    /// isued to replace existing animations for the value.
     fn id(&self) -> Id;
+
+    /// Implement this to return `self` to allow the animation to be modified while
+    /// running, for example to retarget it (see `SpringExt`).
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any> {
+        return None;
+    }
 }
 
-/// A simple animation easing a primitive to a new value. See `HistPrimEaseExt`
-/// which adds a method to `HistPrim` to start easings.
-pub struct HistPrimEaseAnimation<
+/// The previous name of `Animation`, from when only `HistPrim` could be animated.
+pub use self::Animation as HistPrimAnimation;
+
+/// A simple animation easing a value to a new value. See `EaseExt` which adds a
+/// method to `Prim` and `HistPrim` to start easings.
+pub struct EaseAnimation<
     S: Copy + EaseUnit + PartialOrd + AddAssign + Div<Output = S>,
     T: PartialEq + Clone + Add<T, Output = T> + Sub<T, Output = T> + Mul<S, Output = T> + 'static,
+    V: Animatable<T>,
 > {
     start: T,
     range: T,
@@ -62,24 +155,28 @@ pub struct HistPrimEaseAnimation<
     duration: S,
     /// 0..1, with 1 reached after duration
     at: S,
-    f: fn(S) -> S,
-    value: WeakHistPrim<T>,
+    f: EaseFn<S>,
+    value: V::Weak,
 }
 
+/// An `EaseAnimation` of a `HistPrim`.
+pub type HistPrimEaseAnimation<S, T> = EaseAnimation<S, T, HistPrim<T>>;
+
 impl<
     S: Copy + EaseUnit + PartialOrd + AddAssign + Div<Output = S>,
     T: PartialEq + Clone + Add<T, Output = T> + Sub<T, Output = T> + Mul<S, Output = T> + 'static,
-> HistPrimEaseAnimation<S, T> {
-    fn new(prim: &HistPrim<T>, end: T, duration: S, f: fn(S) -> S) -> HistPrimEaseAnimation<S, T> {
-        let start = prim.get();
+    V: Animatable<T>,
+> EaseAnimation<S, T, V> {
+    fn new(value: &V, end: T, duration: S, f: impl Fn(S) -> S + 'static) -> EaseAnimation<S, T, V> {
+        let start = value.animation_get();
         let range = end - start.clone();
-        return HistPrimEaseAnimation {
+        return EaseAnimation {
             start: start,
             range: range,
             duration: duration,
-            f: f,
+            f: Box::new(f),
             at: S::to_ease_unit(0f64),
-            value: prim.weak(),
+            value: value.animation_weak(),
         };
     }
 }
@@ -87,58 +184,507 @@ impl<
 impl<
     S: Copy + EaseUnit + PartialOrd + AddAssign + Div<Output = S>,
     T: PartialEq + Clone + Add<T, Output = T> + Sub<T, Output = T> + Mul<S, Output = T> + 'static,
-> HistPrimAnimation for HistPrimEaseAnimation<S, T> {
+    V: Animatable<T>,
+> Animation for EaseAnimation<S, T, V> {
     fn update(&mut self, pc: &mut ProcessingContext, delta: f64) -> bool {
-        let Some(value) = self.value.upgrade() else {
+        let Some(value) = V::animation_upgrade(&self.value) else {
             return false;
         };
         self.at += S::to_ease_unit(delta) / self.duration;
         if self.at >= S::to_ease_unit(1f64) {
-            value.set(pc, self.start.clone() + self.range.clone());
+            value.animation_set(pc, self.start.clone() + self.range.clone());
             return false;
         }
-        value.set(pc, self.start.clone() + self.range.clone() * (self.f)(self.at));
+        value.animation_set(pc, self.start.clone() + self.range.clone() * (self.f)(self.at));
         return true;
     }
 
     fn id(&self) -> Id {
-        return self.value.upgrade().map(|v| v.0.id).unwrap_or(NULL_ID);
+        return V::animation_upgrade(&self.value).map(|v| v.animation_id()).unwrap_or(NULL_ID);
     }
 }
 
-/// Adds the method `set_ease` for animation to `HistPrim` to parallel `set`.
-/// `duration` is in milliseconds. `f` is a function that takes an input of `0..1`
-/// representing linear progress of the easing and returns another `0..1`
-/// representing the eased visual progress, as the methods in `ezing`.
-pub trait HistPrimEaseExt<
+/// Adds the method `set_ease` for animation to `Prim`, `HistPrim`, and other
+/// `Animatable` values to parallel `set`. `duration` is in milliseconds. `f` is a
+/// function or closure that takes an input of `0..1` representing linear progress
+/// of the easing and returns another `0..1` representing the eased visual progress,
+/// like the functions in `easing`.
+pub trait EaseExt<
     S,
     T: PartialEq + Clone + Add<T, Output = T> + Sub<T, Output = T> + Mul<S, Output = T> + 'static,
 > {
-    fn set_ease(&self, a: &Animator, end: T, duration: S, f: fn(S) -> S);
+    fn set_ease(&self, a: &Animator, end: T, duration: S, f: impl Fn(S) -> S + 'static) -> AnimationHandle;
 }
 
+/// The previous name of `EaseExt`, from when only `HistPrim` could be animated.
+pub use self::EaseExt as HistPrimEaseExt;
+
 impl<
     T: PartialEq + Clone + Add<T, Output = T> + Sub<T, Output = T> + Mul<f32, Output = T> + 'static,
-> HistPrimEaseExt<f32, T> for HistPrim<T> {
-    fn set_ease(&self, a: &Animator, end: T, duration: f32, f: fn(f32) -> f32) {
-        a.start(HistPrimEaseAnimation::new(self, end, duration, f));
+    V: Animatable<T>,
+> EaseExt<f32, T> for V {
+    fn set_ease(&self, a: &Animator, end: T, duration: f32, f: impl Fn(f32) -> f32 + 'static) -> AnimationHandle {
+        return a.start(EaseAnimation::new(self, end, duration, f));
     }
 }
 
 impl<
     T: PartialEq + Clone + Add<T, Output = T> + Sub<T, Output = T> + Mul<f64, Output = T> + 'static,
-> HistPrimEaseExt<f64, T> for HistPrim<T> {
-    fn set_ease(&self, a: &Animator, end: T, duration: f64, f: fn(f64) -> f64) {
-        a.start(HistPrimEaseAnimation::new(self, end, duration, f));
+    V: Animatable<T>,
+> EaseExt<f64, T> for V {
+    fn set_ease(&self, a: &Animator, end: T, duration: f64, f: impl Fn(f64) -> f64 + 'static) -> AnimationHandle {
+        return a.start(EaseAnimation::new(self, end, duration, f));
+    }
+}
+
+/// A value that can be animated with a spring (see `SpringAnimation`).  Implement
+/// this for vector types to animate multiple dimensions together.
+pub trait SpringUnit: PartialEq + Clone + 'static {
+    fn zero() -> Self;
+
+    /// `self + other * scale`
+    fn add_scaled(&self, other: &Self, scale: f64) -> Self;
+
+    /// `self - other`
+    fn difference(&self, other: &Self) -> Self;
+
+    /// The length of the value, for determining when the spring comes to rest.
+    fn magnitude(&self) -> f64;
+}
+
+impl SpringUnit for f32 {
+    fn zero() -> Self {
+        return 0.;
+    }
+
+    fn add_scaled(&self, other: &Self, scale: f64) -> Self {
+        return self + other * scale as f32;
+    }
+
+    fn difference(&self, other: &Self) -> Self {
+        return self - other;
+    }
+
+    fn magnitude(&self) -> f64 {
+        return self.abs() as f64;
+    }
+}
+
+impl SpringUnit for f64 {
+    fn zero() -> Self {
+        return 0.;
+    }
+
+    fn add_scaled(&self, other: &Self, scale: f64) -> Self {
+        return self + other * scale;
+    }
+
+    fn difference(&self, other: &Self) -> Self {
+        return self - other;
+    }
+
+    fn magnitude(&self) -> f64 {
+        return self.abs();
+    }
+}
+
+/// Spring parameters.  Stiffness and damping are per second.
+#[derive(Clone, Copy, Debug)]
+pub struct SpringConfig {
+    pub stiffness: f64,
+    pub damping: f64,
+    pub mass: f64,
+    /// The spring is done when the distance to the target is less than this...
+    pub rest_distance: f64,
+    /// ... and the speed is less than this (units per second).
+    pub rest_velocity: f64,
+}
+
+impl SpringConfig {
+    pub fn new(stiffness: f64, damping: f64, mass: f64) -> Self {
+        return SpringConfig {
+            stiffness: stiffness,
+            damping: damping,
+            mass: mass,
+            ..Default::default()
+        };
+    }
+}
+
+impl Default for SpringConfig {
+    fn default() -> Self {
+        return SpringConfig {
+            stiffness: 170.,
+            damping: 26.,
+            mass: 1.,
+            rest_distance: 0.01,
+            rest_velocity: 0.01,
+        };
+    }
+}
+
+/// Animates a value towards a target as if attached to it by a damped spring.  See
+/// `SpringExt` which adds a method to `Prim` and `HistPrim` to start springs.
+pub struct SpringAnimation<T: SpringUnit, V: Animatable<T>> {
+    position: T,
+    target: T,
+    /// Units per second
+    velocity: T,
+    config: SpringConfig,
+    value: V::Weak,
+}
+
+impl<T: SpringUnit, V: Animatable<T>> SpringAnimation<T, V> {
+    /// Start from the current value at rest.
+    pub fn new(value: &V, target: T, config: SpringConfig) -> Self {
+        return SpringAnimation {
+            position: value.animation_get(),
+            target: target,
+            velocity: T::zero(),
+            config: config,
+            value: value.animation_weak(),
+        };
+    }
+
+    /// Change the target and config without changing the current velocity.
+    pub fn retarget(&mut self, target: T, config: SpringConfig) {
+        self.target = target;
+        self.config = config;
+    }
+
+    pub fn velocity(&self) -> &T {
+        return &self.velocity;
+    }
+}
+
+impl<T: SpringUnit, V: Animatable<T>> Animation for SpringAnimation<T, V> {
+    fn update(&mut self, pc: &mut ProcessingContext, delta_ms: f64) -> bool {
+        let Some(value) = V::animation_upgrade(&self.value) else {
+            return false;
+        };
+
+        // Semi-implicit euler with small fixed steps for stability with stiff springs
+        let steps = (delta_ms / 4.).ceil().max(1.);
+        let dt = delta_ms / 1000. / steps;
+        for _ in 0 .. steps as usize {
+            let displacement = self.position.difference(&self.target);
+            let force =
+                T::zero()
+                    .add_scaled(&displacement, -self.config.stiffness)
+                    .add_scaled(&self.velocity, -self.config.damping);
+            self.velocity = self.velocity.add_scaled(&force, dt / self.config.mass);
+            self.position = self.position.add_scaled(&self.velocity, dt);
+        }
+        if self.position.difference(&self.target).magnitude() < self.config.rest_distance &&
+            self.velocity.magnitude() < self.config.rest_velocity {
+            value.animation_set(pc, self.target.clone());
+            return false;
+        }
+        value.animation_set(pc, self.position.clone());
+        return true;
+    }
+
+    fn id(&self) -> Id {
+        return V::animation_upgrade(&self.value).map(|v| v.animation_id()).unwrap_or(NULL_ID);
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any> {
+        return Some(self);
+    }
+}
+
+/// Adds the method `set_spring` for animation to `Prim`, `HistPrim`, and other
+/// `Animatable` values to parallel `set`.  If a spring is already animating the
+/// value it's retargeted, keeping its velocity, otherwise a new spring is started
+/// from rest.
+pub trait SpringExt<T: SpringUnit> {
+    fn set_spring(&self, a: &Animator, target: T, config: SpringConfig) -> AnimationHandle;
+}
+
+impl<T: SpringUnit, V: Animatable<T>> SpringExt<T> for V {
+    fn set_spring(&self, a: &Animator, target: T, config: SpringConfig) -> AnimationHandle {
+        {
+            let mut a2 = a.0.borrow_mut();
+            if let Some((anim, handle)) = a2.interp.get_mut(&self.animation_id()) {
+                let existing = anim.as_any_mut().and_then(|anim| anim.downcast_mut::<SpringAnimation<T, V>>());
+                if let Some(existing) = existing {
+                    existing.retarget(target, config);
+                    return handle.clone();
+                }
+            }
+        }
+        return a.start(SpringAnimation::new(self, target, config));
+    }
+}
+
+/// A target value in a keyframe track (see `Timeline::keyframes`).  `at` is the
+/// normalized time `0..1` within the track, and `f` is the easing used for the
+/// transition from the previous keyframe to this one.
+pub struct Keyframe<S, T> {
+    pub at: S,
+    pub value: T,
+    pub f: EaseFn<S>,
+}
+
+impl<S, T> Keyframe<S, T> {
+    pub fn new(at: S, value: T, f: impl Fn(S) -> S + 'static) -> Self {
+        return Keyframe {
+            at: at,
+            value: value,
+            f: Box::new(f),
+        };
+    }
+}
+
+trait Track {
+    /// Set values for time `t` (milliseconds) within the track, `0..=duration`.
+    fn sample(&mut self, pc: &mut ProcessingContext, t: f64);
+}
+
+struct DelayTrack;
+
+impl Track for DelayTrack {
+    fn sample(&mut self, _pc: &mut ProcessingContext, _t: f64) { }
+}
+
+struct KeyframeTrack<S, T, V: Animatable<T>> {
+    duration: f64,
+    /// The value when the track started, for the transition to the first keyframe
+    from: Option<T>,
+    keyframes: Vec<Keyframe<S, T>>,
+    value: V::Weak,
+}
+
+impl<
+    S: Copy + EaseUnit + PartialOrd + Sub<Output = S> + Div<Output = S> + 'static,
+    T: PartialEq + Clone + Add<T, Output = T> + Sub<T, Output = T> + Mul<S, Output = T> + 'static,
+    V: Animatable<T>,
+> Track for KeyframeTrack<S, T, V> {
+    fn sample(&mut self, pc: &mut ProcessingContext, t: f64) {
+        let Some(value) = V::animation_upgrade(&self.value) else {
+            return;
+        };
+        let from = self.from.get_or_insert_with(|| value.animation_get()).clone();
+        let at = S::to_ease_unit(if self.duration > 0. {
+            t / self.duration
+        } else {
+            1.
+        });
+        let mut prev_at = S::to_ease_unit(0.);
+        let mut prev_value = from;
+        for keyframe in &self.keyframes {
+            if keyframe.at < at {
+                prev_at = keyframe.at;
+                prev_value = keyframe.value.clone();
+                continue;
+            }
+            let span = keyframe.at - prev_at;
+            let progress = if span > S::to_ease_unit(0.) {
+                (keyframe.f)((at - prev_at) / span)
+            } else {
+                S::to_ease_unit(1.)
+            };
+            value.animation_set(pc, prev_value.clone() + (keyframe.value.clone() - prev_value) * progress);
+            return;
+        }
+        value.animation_set(pc, prev_value);
+    }
+}
+
+struct SequenceTrack(Vec<Timeline>);
+
+impl Track for SequenceTrack {
+    fn sample(&mut self, pc: &mut ProcessingContext, t: f64) {
+        let mut start = 0.;
+        for child in &mut self.0 {
+            if t < start {
+                break;
+            }
+            child.sample_(pc, t - start);
+            start += child.duration;
+        }
+    }
+}
+
+struct ParallelTrack(Vec<Timeline>);
+
+impl Track for ParallelTrack {
+    fn sample(&mut self, pc: &mut ProcessingContext, t: f64) {
+        for child in &mut self.0 {
+            child.sample_(pc, t);
+        }
+    }
+}
+
+/// A composition of animations, built from keyframe tracks, delays, and sequential
+/// and parallel groups.  Start it with `Animator::start` to run the whole timeline
+/// from a single `update`.  Times are in milliseconds.
+///
+/// Each keyframe track starts from the value it animates at the time the track
+/// begins, so later steps in a sequence continue from where earlier steps left
+/// them.
+pub struct Timeline {
+    id: Id,
+    duration: f64,
+    /// For the root, the time since the timeline started
+    elapsed: f64,
+    done: bool,
+    track: Box<dyn Track>,
+}
+
+impl Timeline {
+    fn new_(duration: f64, track: impl Track + 'static) -> Timeline {
+        return Timeline {
+            id: new_id(),
+            duration: duration,
+            elapsed: 0.,
+            done: false,
+            track: Box::new(track),
+        };
+    }
+
+    /// A step that does nothing, to space out other steps in a sequence.
+    pub fn delay(duration: f64) -> Timeline {
+        return Timeline::new_(duration, DelayTrack);
+    }
+
+    /// Animate `value` through `keyframes` over `duration`.  `keyframes` must be
+    /// ordered by `at`.
+    pub fn keyframes<
+        S: Copy + EaseUnit + PartialOrd + Sub<Output = S> + Div<Output = S> + 'static,
+        T: PartialEq + Clone + Add<T, Output = T> + Sub<T, Output = T> + Mul<S, Output = T> + 'static,
+        V: Animatable<T>,
+    >(value: &V, duration: f64, keyframes: Vec<Keyframe<S, T>>) -> Timeline {
+        return Timeline::new_(duration, KeyframeTrack::<S, T, V> {
+            duration: duration,
+            from: None,
+            keyframes: keyframes,
+            value: value.animation_weak(),
+        });
+    }
+
+    /// Run `children` one after another.
+    pub fn sequence(children: Vec<Timeline>) -> Timeline {
+        let duration = children.iter().map(|c| c.duration).sum();
+        return Timeline::new_(duration, SequenceTrack(children));
+    }
+
+    /// Run `children` at the same time.  This lasts as long as the longest child.
+    pub fn parallel(children: Vec<Timeline>) -> Timeline {
+        let duration = children.iter().map(|c| c.duration).fold(0., f64::max);
+        return Timeline::new_(duration, ParallelTrack(children));
+    }
+
+    /// Milliseconds
+    pub fn duration(&self) -> f64 {
+        return self.duration;
+    }
+
+    /// Sample at time `t` since this step started, stopping once the end was sampled.
+    fn sample_(&mut self, pc: &mut ProcessingContext, t: f64) {
+        if self.done {
+            return;
+        }
+        self.track.sample(pc, t.min(self.duration));
+        self.done = t >= self.duration;
+    }
+}
+
+impl Animation for Timeline {
+    fn update(&mut self, pc: &mut ProcessingContext, delta_ms: f64) -> bool {
+        self.elapsed += delta_ms;
+        self.sample_(pc, self.elapsed);
+        return !self.done;
+    }
+
+    /// Timelines may animate multiple values, so they have their own id and don't
+    /// replace animations of the individual values.
+    fn id(&self) -> Id {
+        return self.id;
+    }
+}
+
+/// The status of an animation started with `Animator::start`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AnimationState {
+    Running,
+    /// The animation reached its end.
+    Finished,
+    /// The animation was stopped with `Animator::cancel` or `Animator::clear`.
+    Cancelled,
+    /// Another animation was started for the same value.
+    Replaced,
+}
+
+type EndCb = Box<dyn FnOnce(&mut ProcessingContext, AnimationState)>;
+type Animations = HashMap<Id, (Box<dyn Animation>, AnimationHandle)>;
+
+struct AnimationHandle_ {
+    state: Cell<AnimationState>,
+    state_prim: Prim<AnimationState>,
+    on_end: RefCell<Option<EndCb>>,
+}
+
+/// A handle for observing an animation started with `Animator::start`.
+///
+/// The end of the animation is delivered to `state_prim` and the `on_end` callback
+/// during `Animator::update`, since modifying values requires an event.  This
+/// includes cancellation and replacement, which are delivered during the next
+/// update.
+#[derive(Clone)]
+pub struct AnimationHandle(Rc<AnimationHandle_>);
+
+impl AnimationHandle {
+    fn new() -> AnimationHandle {
+        return AnimationHandle(Rc::new(AnimationHandle_ {
+            state: Cell::new(AnimationState::Running),
+            state_prim: Prim::new(AnimationState::Running),
+            on_end: RefCell::new(None),
+        }));
+    }
+
+    /// The current state.  Unlike `state_prim` this changes immediately when the
+    /// animation is cancelled or replaced.
+    pub fn state(&self) -> AnimationState {
+        return self.0.state.get();
+    }
+
+    /// The state as a value that links can depend on.
+    pub fn state_prim(&self) -> Prim<AnimationState> {
+        return self.0.state_prim.clone();
+    }
+
+    /// Set a callback to call when the animation ends for any reason, replacing any
+    /// previous callback.  This must be set before the end is delivered in `update`.
+    pub fn on_end(&self, cb: impl FnOnce(&mut ProcessingContext, AnimationState) + 'static) {
+        *self.0.on_end.borrow_mut() = Some(Box::new(cb));
+    }
+
+    fn end_(&self, state: AnimationState) {
+        if self.0.state.get() == AnimationState::Running {
+            self.0.state.set(state);
+        }
+    }
+
+    fn deliver_(&self, pc: &mut ProcessingContext) {
+        let state = self.0.state.get();
+        self.0.state_prim.set(pc, state);
+        let cb = self.0.on_end.borrow_mut().take();
+        if let Some(cb) = cb {
+            cb(pc, state);
+        }
     }
 }
 
 /// Manages animations. After creating, start some animations then call `update`
 /// regularly.  `trigger_cb` is a callback that's called whenever a new animation
-/// is started, which can be used to start real-time updates or whatever.
+/// is started or an end needs to be delivered, which can be used to start real-time
+/// updates or whatever.
 pub struct Animator_ {
-    interp: HashMap<Id, Box<dyn HistPrimAnimation>>,
-    interp_backbuf: Option<HashMap<Id, Box<dyn HistPrimAnimation>>>,
+    interp: Animations,
+    interp_backbuf: Option<Animations>,
+    /// Animations that were cancelled or replaced, to deliver in the next update
+    ended: Vec<AnimationHandle>,
     anim_cb: Option<Box<dyn FnMut() -> ()>>,
 }
 
@@ -150,6 +696,7 @@ impl Animator {
         return Animator(Rc::new(RefCell::new(Animator_ {
             interp: Default::default(),
             interp_backbuf: Some(Default::default()),
+            ended: vec![],
             anim_cb: None,
         })));
     }
@@ -158,23 +705,51 @@ impl Animator {
         self.0.borrow_mut().anim_cb = Some(Box::new(trigger_cb));
     }
 
-    /// Start a new animation for the primitive, replacing any existing animation.
-    pub fn start(&self, animation: impl HistPrimAnimation + 'static) {
-        self.0.borrow_mut().interp.insert(animation.id(), Box::new(animation));
+    fn trigger_(&self) {
         if let Some(cb) = &mut self.0.borrow_mut().anim_cb {
             cb();
         }
     }
 
-    /// Stop easing a primitive. If the primitive isn't being smoothed this does
-    /// nothing. The primitive will retain the current value.
-    pub fn cancel<T: PartialEq + Clone + 'static>(&self, prim: &HistPrim<T>) {
-        self.0.borrow_mut().interp.remove(&prim.0.id);
+    /// Start a new animation for the value, replacing any existing animation.
+    pub fn start(&self, animation: impl Animation + 'static) -> AnimationHandle {
+        let handle = AnimationHandle::new();
+        {
+            let mut self2 = self.0.borrow_mut();
+            if let Some((_, old)) = self2.interp.insert(animation.id(), (Box::new(animation), handle.clone())) {
+                old.end_(AnimationState::Replaced);
+                self2.ended.push(old);
+            }
+        }
+        self.trigger_();
+        return handle;
+    }
+
+    /// Stop easing a value. If the value isn't being smoothed this does nothing. The
+    /// value will retain the current value.
+    pub fn cancel<T>(&self, value: &impl Animatable<T>) {
+        let removed = self.0.borrow_mut().interp.remove(&value.animation_id());
+        if let Some((_, handle)) = removed {
+            handle.end_(AnimationState::Cancelled);
+            self.0.borrow_mut().ended.push(handle);
+            self.trigger_();
+        }
     }
 
     /// Stop all current easings.
     pub fn clear(&self) {
-        self.0.borrow_mut().interp.clear();
+        let cancelled = {
+            let mut self2 = self.0.borrow_mut();
+            let handles = self2.interp.drain().map(|(_, (_, handle))| handle).collect::<Vec<_>>();
+            for handle in &handles {
+                handle.end_(AnimationState::Cancelled);
+            }
+            self2.ended.extend(handles.iter().cloned());
+            !handles.is_empty()
+        };
+        if cancelled {
+            self.trigger_();
+        }
     }
 
     /// Updates interpolating nodes and processes the graph as usual. Call from
@@ -190,13 +765,37 @@ impl Animator {
                 interp
             };
             let mut alive = false;
-            for (id, mut l) in interp.drain() {
-                if l.update(pc, delta_ms) {
-                    alive = true;
-                    self.0.borrow_mut().interp.insert(id, l);
+            for (id, (mut l, handle)) in interp.drain() {
+                if !l.update(pc, delta_ms) {
+                    handle.end_(AnimationState::Finished);
+                    handle.deliver_(pc);
+                    continue;
+                }
+                alive = true;
+
+                // Animations started during the update replace this one
+                let replaced = match self.0.borrow_mut().interp.entry(id) {
+                    Entry::Occupied(_) => true,
+                    Entry::Vacant(e) => {
+                        e.insert((l, handle.clone()));
+                        false
+                    },
+                };
+                if replaced {
+                    handle.end_(AnimationState::Replaced);
+                    handle.deliver_(pc);
                 }
             }
             self.0.borrow_mut().interp_backbuf = Some(interp);
+            loop {
+                let ended = std::mem::take(&mut self.0.borrow_mut().ended);
+                if ended.is_empty() {
+                    break;
+                }
+                for handle in ended {
+                    handle.deliver_(pc);
+                }
+            }
             out = Some(alive);
         });
         return out.expect(
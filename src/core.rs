@@ -10,6 +10,10 @@ use {
             HashMap,
             HashSet,
         },
+        sync::atomic::{
+            AtomicUsize,
+            Ordering,
+        },
     },
 };
 
@@ -18,6 +22,14 @@ use {
 pub type Id = usize;
 pub const NULL_ID: Id = 0;
 
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+/// Allocate an id, unique among all ids allocated in the process.  Ids don't depend
+/// on the graph so values can be created outside of an event.
+pub(crate) fn new_id() -> Id {
+    return NEXT_ID.fetch_add(1, Ordering::Relaxed);
+}
+
 pub trait ValueTrait {
     fn next_links(&self) -> Vec<Link>;
 }
@@ -83,22 +95,34 @@ impl Link {
             inner: Box::new(inner),
         }));
         pc.1.step1_stacked_links.push((true, out.clone()));
+        pc.1.dirty_links.insert(id);
         return out;
     }
 }
 
 pub struct _Context {
     pub(crate) step1_stacked_links: Vec<(bool, Link)>,
+    /// Links that are new or have an input that was modified during the current event.
+    /// Other links in the affected subgraph are skipped.
+    pub(crate) dirty_links: HashSet<Id>,
     pub(crate) cleanup: Vec<Rc<dyn Cleanup>>,
     pub(crate) processing: bool,
-    ids: usize,
 }
 
 impl _Context {
     pub(crate) fn take_id(&mut self) -> Id {
-        let id = self.ids;
-        self.ids += 1;
-        return id;
+        return new_id();
+    }
+
+    /// Called by values when they're first modified during an event, with the links
+    /// downstream of the value.
+    pub(crate) fn mark_changed(&mut self, next: Vec<Link>) {
+        for l in next {
+            self.dirty_links.insert(l.0.id);
+            if !self.processing {
+                self.step1_stacked_links.push((true, l));
+            }
+        }
     }
 }
 
@@ -115,9 +139,9 @@ impl EventGraph {
     pub fn new() -> EventGraph {
         return EventGraph(Rc::new(RefCell::new(_Context {
             step1_stacked_links: Default::default(),
+            dirty_links: Default::default(),
             cleanup: vec![],
             processing: false,
-            ids: 1,
         })));
     }
 
@@ -240,7 +264,7 @@ impl EventGraph {
                         step2_stacked_links.push((true, prev_link));
                     }
                     step2_seen_up.clear();
-                } else {
+                } else if s.dirty_links.contains(&link.0.id) {
                     (link.0.inner).call(&mut ProcessingContext(self, &mut s));
                 }
             }
@@ -251,6 +275,7 @@ impl EventGraph {
         for p in s.cleanup.drain(0..) {
             p.clean();
         }
+        s.dirty_links.clear();
         s.processing = false;
         return Some(out);
     }
@@ -0,0 +1,235 @@
+//! Easing functions for `set_ease` and timeline keyframes.  Each takes linear
+//! progress `0..1` and returns eased progress, which is `0` at `0` and `1` at `1`
+//! but may overshoot in between (ex: `back`, `elastic`).
+//!
+//! The Penner set has `_in`, `_out`, and `_in_out` variants.  Functions that need
+//! parameters (`cubic_bezier`, `steps`, `piecewise_linear`) return closures.  Any
+//! other `Fn(S) -> S` can be used as a custom easing.
+use std::f64::consts::PI;
+use crate::animate::EaseUnit;
+
+pub fn linear<S: EaseUnit>(t: S) -> S {
+    return t;
+}
+
+macro_rules! penner {
+    ($curve: ident, $in: ident, $out: ident, $in_out: ident) => {
+        pub fn $in<S: EaseUnit + Into<f64>>(t: S) -> S {
+            return S::to_ease_unit($curve(t.into()));
+        }
+
+        pub fn $out<S: EaseUnit + Into<f64>>(t: S) -> S {
+            return S::to_ease_unit(1. - $curve(1. - t.into()));
+        }
+
+        pub fn $in_out<S: EaseUnit + Into<f64>>(t: S) -> S {
+            let t = t.into();
+            return S::to_ease_unit(if t < 0.5 {
+                $curve(t * 2.) / 2.
+            } else {
+                1. - $curve((1. - t) * 2.) / 2.
+            });
+        }
+    };
+}
+
+fn quad(t: f64) -> f64 {
+    return t * t;
+}
+
+fn cubic(t: f64) -> f64 {
+    return t * t * t;
+}
+
+fn quart(t: f64) -> f64 {
+    return t * t * t * t;
+}
+
+fn quint(t: f64) -> f64 {
+    return t * t * t * t * t;
+}
+
+fn sine(t: f64) -> f64 {
+    return 1. - (t * PI / 2.).cos();
+}
+
+fn expo(t: f64) -> f64 {
+    if t <= 0. {
+        return 0.;
+    }
+    return 2f64.powf(10. * t - 10.);
+}
+
+fn circ(t: f64) -> f64 {
+    return 1. - (1. - t * t).max(0.).sqrt();
+}
+
+fn back(t: f64) -> f64 {
+    let c1 = 1.70158;
+    return (c1 + 1.) * t * t * t - c1 * t * t;
+}
+
+fn elastic(t: f64) -> f64 {
+    if t <= 0. {
+        return 0.;
+    }
+    if t >= 1. {
+        return 1.;
+    }
+    return -(2f64.powf(10. * t - 10.)) * ((10. * t - 10.75) * (2. * PI / 3.)).sin();
+}
+
+fn bounce(t: f64) -> f64 {
+    let t = 1. - t;
+    let n1 = 7.5625;
+    let d1 = 2.75;
+    let out = if t < 1. / d1 {
+        n1 * t * t
+    } else if t < 2. / d1 {
+        let t = t - 1.5 / d1;
+        n1 * t * t + 0.75
+    } else if t < 2.5 / d1 {
+        let t = t - 2.25 / d1;
+        n1 * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / d1;
+        n1 * t * t + 0.984375
+    };
+    return 1. - out;
+}
+
+penner!(quad, quad_in, quad_out, quad_in_out);
+penner!(cubic, cubic_in, cubic_out, cubic_in_out);
+penner!(quart, quart_in, quart_out, quart_in_out);
+penner!(quint, quint_in, quint_out, quint_in_out);
+penner!(sine, sine_in, sine_out, sine_in_out);
+penner!(expo, expo_in, expo_out, expo_in_out);
+penner!(circ, circ_in, circ_out, circ_in_out);
+penner!(back, back_in, back_out, back_in_out);
+penner!(elastic, elastic_in, elastic_out, elastic_in_out);
+penner!(bounce, bounce_in, bounce_out, bounce_in_out);
+
+/// A cubic bezier curve from `(0, 0)` to `(1, 1)` with control points `(x1, y1)`
+/// and `(x2, y2)`, like CSS `cubic-bezier()`.  `x1` and `x2` must be in `0..=1`.
+pub fn cubic_bezier<S: EaseUnit + Into<f64>>(x1: f64, y1: f64, x2: f64, y2: f64) -> impl Fn(S) -> S + Clone {
+    fn at(a1: f64, a2: f64, t: f64) -> f64 {
+        let u = 1. - t;
+        return 3. * u * u * t * a1 + 3. * u * t * t * a2 + t * t * t;
+    }
+
+    fn slope(a1: f64, a2: f64, t: f64) -> f64 {
+        let u = 1. - t;
+        return 3. * u * u * a1 + 6. * u * t * (a2 - a1) + 3. * t * t * (1. - a2);
+    }
+
+    return move |x: S| {
+        let x = x.into();
+        if x <= 0. || x >= 1. {
+            return S::to_ease_unit(x.clamp(0., 1.));
+        }
+
+        // Find the curve parameter for `x` with Newton's method, falling back to
+        // bisection if it doesn't converge (ex: flat slope)
+        let mut t = x;
+        let mut found = false;
+        for _ in 0 .. 8 {
+            let error = at(x1, x2, t) - x;
+            if error.abs() < 1e-7 {
+                found = true;
+                break;
+            }
+            let d = slope(x1, x2, t);
+            if d.abs() < 1e-6 {
+                break;
+            }
+            t -= error / d;
+        }
+        if !found || !(0. ..= 1.).contains(&t) {
+            let mut low = 0.;
+            let mut high = 1.;
+            t = x;
+            for _ in 0 .. 64 {
+                let error = at(x1, x2, t) - x;
+                if error.abs() < 1e-7 {
+                    break;
+                }
+                if error > 0. {
+                    high = t;
+                } else {
+                    low = t;
+                }
+                t = (low + high) / 2.;
+            }
+        }
+        return S::to_ease_unit(at(y1, y2, t));
+    };
+}
+
+/// CSS `ease`
+pub fn ease<S: EaseUnit + Into<f64>>(t: S) -> S {
+    return cubic_bezier(0.25, 0.1, 0.25, 1.)(t);
+}
+
+/// Where the jumps happen in `steps_with`, like the CSS `steps()` jump terms.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StepPosition {
+    /// The first jump happens at the start
+    Start,
+    /// The last jump happens at the end
+    End,
+    /// No jump at the start or end, `n` must be at least 2
+    None,
+    /// Jumps at both the start and end
+    Both,
+}
+
+/// Jump between `n` evenly spaced levels, like CSS `steps(n, jump-end)`.
+pub fn steps<S: EaseUnit + Into<f64>>(n: usize) -> impl Fn(S) -> S + Clone {
+    return steps_with(n, StepPosition::End);
+}
+
+/// Jump between evenly spaced levels, like CSS `steps(n, position)`.
+pub fn steps_with<S: EaseUnit + Into<f64>>(n: usize, position: StepPosition) -> impl Fn(S) -> S + Clone {
+    let n = n.max(1) as f64;
+    return move |t: S| {
+        let t = t.into();
+        if t >= 1. {
+            return S::to_ease_unit(1.);
+        }
+        if t < 0. {
+            return S::to_ease_unit(0.);
+        }
+        let step = (t * n).floor();
+        return S::to_ease_unit(match position {
+            StepPosition::Start => (step + 1.) / n,
+            StepPosition::End => step / n,
+            StepPosition::None => step / (n - 1.).max(1.),
+            StepPosition::Both => (step + 1.) / (n + 1.),
+        }.min(1.));
+    };
+}
+
+/// A custom curve through `points` of `(progress, eased progress)` ordered by
+/// progress, interpolated linearly like CSS `linear()`.  Progress outside the points
+/// uses the first or last eased progress.
+pub fn piecewise_linear<S: EaseUnit + Into<f64>>(points: Vec<(f64, f64)>) -> impl Fn(S) -> S + Clone {
+    return move |t: S| {
+        let t = t.into();
+        let Some(first) = points.first() else {
+            return S::to_ease_unit(t);
+        };
+        if t <= first.0 {
+            return S::to_ease_unit(first.1);
+        }
+        for pair in points.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            if t <= b.0 {
+                if b.0 <= a.0 {
+                    return S::to_ease_unit(b.1);
+                }
+                return S::to_ease_unit(a.1 + (b.1 - a.1) * (t - a.0) / (b.0 - a.0));
+            }
+        }
+        return S::to_ease_unit(points.last().unwrap().1);
+    };
+}
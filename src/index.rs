@@ -0,0 +1,224 @@
+use std::{
+    rc::{
+        Rc,
+        Weak,
+    },
+    cell::{
+        RefCell,
+        Ref,
+    },
+    collections::HashMap,
+    hash::Hash,
+    ops::Deref,
+};
+use crate::{
+    core::{
+        ValueTrait,
+        ProcessingContext,
+        Cleanup,
+        Link_,
+        IntoValue,
+        Value,
+    },
+    Link,
+};
+
+pub struct IndexMut_<K: Clone + Eq + Hash> {
+    /// The key of each element of the indexed list
+    keys: std::vec::Vec<K>,
+    /// The sorted positions of the elements with each key
+    positions: HashMap<K, std::vec::Vec<usize>>,
+    first_change: bool,
+    next: std::vec::Vec<Weak<Link_>>,
+    /// The link that keeps this index updated
+    links: std::vec::Vec<Link>,
+}
+
+impl<K: Clone + Eq + Hash> IndexMut_<K> {
+    fn next(&mut self) -> Vec<crate::Link> {
+        let mut out = vec![];
+        out.reserve(self.next.len());
+        self.next.retain_mut(|e| {
+            match e.upgrade() {
+                Some(e) => {
+                    out.push(Link(e.clone()));
+                    return true;
+                },
+                None => {
+                    return false;
+                },
+            }
+        });
+        return out;
+    }
+
+    fn add_position(&mut self, key: K, index: usize) {
+        let positions = self.positions.entry(key).or_default();
+        let at = positions.partition_point(|p| *p < index);
+        positions.insert(at, index);
+    }
+
+    fn remove_position(&mut self, key: &K, index: usize) {
+        let positions = self.positions.get_mut(key).unwrap();
+        let at = positions.partition_point(|p| *p < index);
+        positions.remove(at);
+        if positions.is_empty() {
+            self.positions.remove(key);
+        }
+    }
+}
+
+struct Index_<K: Clone + Eq + Hash> {
+    mut_: RefCell<IndexMut_<K>>,
+}
+
+impl<K: Clone + Eq + Hash> ValueTrait for Index_<K> {
+    fn next_links(&self) -> Vec<crate::Link> {
+        return self.mut_.borrow_mut().next();
+    }
+}
+
+impl<K: Clone + Eq + Hash> Cleanup for Index_<K> {
+    fn clean(&self) {
+        self.mut_.borrow_mut().first_change = true;
+    }
+}
+
+/// A value derived from a list with the positions of its elements by key, kept in
+/// sync with the list's changes.  Create it with `List::index_by`.  Use it as a link
+/// input to look up elements in the current state of the list.
+#[derive(Clone)]
+pub struct Index<K: Clone + Eq + Hash>(Rc<Index_<K>>);
+
+#[derive(Clone)]
+pub struct WeakIndex<K: Clone + Eq + Hash>(Weak<Index_<K>>);
+
+impl<K: Clone + Eq + Hash + 'static> Index<K> {
+    pub(crate) fn new_(keys: std::vec::Vec<K>) -> Self {
+        let mut self2 = IndexMut_ {
+            keys: vec![],
+            positions: HashMap::new(),
+            first_change: true,
+            next: vec![],
+            links: vec![],
+        };
+        for (i, key) in keys.iter().enumerate() {
+            self2.positions.entry(key.clone()).or_default().push(i);
+        }
+        self2.keys = keys;
+        return Index(Rc::new(Index_ { mut_: RefCell::new(self2) }));
+    }
+
+    /// Used internally by the `link!` macro to establish graph edges between an input
+    /// value and the link.
+    pub fn add_next(&self, link: &Link) {
+        self.0.mut_.borrow_mut().next.push(Rc::downgrade(&link.0));
+    }
+
+    /// Get a weak reference to the index.
+    pub fn weak(&self) -> WeakIndex<K> {
+        return WeakIndex(Rc::downgrade(&self.0));
+    }
+
+    pub(crate) fn own_link(&self, link: Link) {
+        self.0.mut_.borrow_mut().links.push(link);
+    }
+
+    fn touch_(&self, self2: &mut IndexMut_<K>, pc: &mut ProcessingContext) {
+        if self2.first_change {
+            self2.first_change = false;
+            pc.1.cleanup.push(self.0.clone());
+            pc.1.mark_changed(self2.next());
+        }
+    }
+
+    /// Mirror a splice of the indexed list.
+    pub(crate) fn splice_(&self, pc: &mut ProcessingContext, offset: usize, remove: usize, add: std::vec::Vec<K>) {
+        let mut self2 = self.0.mut_.borrow_mut();
+        self.touch_(&mut self2, pc);
+        let removed = self2.keys.splice(offset .. offset + remove, add.iter().cloned()).collect::<Vec<_>>();
+        for (i, key) in removed.iter().enumerate() {
+            self2.remove_position(key, offset + i);
+        }
+        if add.len() != remove {
+            let end = offset + remove;
+            for positions in self2.positions.values_mut() {
+                for p in positions.iter_mut().rev() {
+                    if *p < end {
+                        break;
+                    }
+                    *p = *p + add.len() - remove;
+                }
+            }
+        }
+        for (i, key) in add.into_iter().enumerate() {
+            self2.add_position(key, offset + i);
+        }
+    }
+
+    /// Mirror a replacement of an element of the indexed list.
+    pub(crate) fn set_(&self, pc: &mut ProcessingContext, index: usize, key: K) {
+        let mut self2 = self.0.mut_.borrow_mut();
+        if self2.keys[index] == key {
+            return;
+        }
+        self.touch_(&mut self2, pc);
+        let old = std::mem::replace(&mut self2.keys[index], key.clone());
+        self2.remove_position(&old, index);
+        self2.add_position(key, index);
+    }
+
+    /// Mirror a move of an element of the indexed list.
+    pub(crate) fn move_(&self, pc: &mut ProcessingContext, from: usize, to: usize) {
+        if from == to {
+            return;
+        }
+        let key = self.0.mut_.borrow().keys[from].clone();
+        self.splice_(pc, from, 1, vec![]);
+        self.splice_(pc, to, 0, vec![key]);
+    }
+
+    /// The position of the first element with `key`, if any.
+    pub fn get(&self, key: &K) -> Option<usize> {
+        return self.0.mut_.borrow().positions.get(key).map(|p| p[0]);
+    }
+
+    /// The positions of all elements with `key`, in order.
+    pub fn get_all(&self, key: &K) -> std::vec::Vec<usize> {
+        return self.0.mut_.borrow().positions.get(key).cloned().unwrap_or_default();
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        return self.0.mut_.borrow().positions.contains_key(key);
+    }
+
+    /// The current positions of all keys.  A `Deref` wrapper around a map of each key
+    /// to the sorted positions of the elements with that key.
+    ///
+    /// Borrows the index, must be released before the indexed list is modified.
+    pub fn borrow_values<'a>(&'a self) -> IndexValuesRef<'a, K> {
+        return IndexValuesRef(self.0.mut_.borrow());
+    }
+}
+
+impl<K: Clone + Eq + Hash + 'static> IntoValue for Index<K> {
+    fn into_value(&self) -> Value {
+        return Value(self.0.clone());
+    }
+}
+
+impl<K: Clone + Eq + Hash + 'static> WeakIndex<K> {
+    pub fn upgrade(&self) -> Option<Index<K>> {
+        return Some(Index(self.0.upgrade()?));
+    }
+}
+
+pub struct IndexValuesRef<'a, K: Clone + Eq + Hash + 'static>(Ref<'a, IndexMut_<K>>);
+
+impl<'a, K: Clone + Eq + Hash + 'static> Deref for IndexValuesRef<'a, K> {
+    type Target = HashMap<K, std::vec::Vec<usize>>;
+
+    fn deref(&self) -> &Self::Target {
+        return &self.0.positions;
+    }
+}
@@ -0,0 +1,370 @@
+use std::{
+    rc::{
+        Rc,
+        Weak,
+    },
+    cell::{
+        RefCell,
+        Ref,
+    },
+    collections::HashMap,
+    hash::Hash,
+    ops::Deref,
+};
+use crate::{
+    core::{
+        ValueTrait,
+        ProcessingContext,
+        Cleanup,
+        Link_,
+        IntoValue,
+        Value,
+    },
+    Link,
+};
+
+/// A single modification to a keyed list.  Indexes in each change are relative to
+/// the state of the list after the previous change was applied.  Values are only
+/// included if the list was created with `new_keep_values`.
+pub enum KeyedChange<K, V> {
+    /// A new element was inserted at `index`.  `value` has the inserted value.
+    Insert {
+        key: K,
+        index: usize,
+        value: Option<V>,
+    },
+    /// The element was removed from `index`.  `old` has the removed value.
+    Remove {
+        key: K,
+        index: usize,
+        old: Option<V>,
+    },
+    /// The element at `from` was removed and reinserted so that it ends up at `to`.
+    Move {
+        key: K,
+        from: usize,
+        to: usize,
+    },
+    /// The element's value was replaced, its position is unchanged.  `value` has the
+    /// new value and `old` the replaced value.
+    Update {
+        key: K,
+        value: Option<V>,
+        old: Option<V>,
+    },
+}
+
+pub struct KeyedListMut_<K: Clone + Eq + Hash, V> {
+    order: std::vec::Vec<K>,
+    values: HashMap<K, V>,
+    changes: std::vec::Vec<KeyedChange<K, V>>,
+    /// Set if values should be included in changes
+    clone_value: Option<fn(&V) -> V>,
+    next: std::vec::Vec<Weak<Link_>>,
+}
+
+impl<K: Clone + Eq + Hash, V> KeyedListMut_<K, V> {
+    fn next(&mut self) -> Vec<crate::Link> {
+        let mut out = vec![];
+        out.reserve(self.next.len());
+        self.next.retain_mut(|e| {
+            match e.upgrade() {
+                Some(e) => {
+                    out.push(Link(e.clone()));
+                    return true;
+                },
+                None => {
+                    return false;
+                },
+            }
+        });
+        return out;
+    }
+
+    /// A copy of `value` to include in a change, if values are kept.
+    fn keep(&self, value: &V) -> Option<V> {
+        return self.clone_value.map(|c| c(value));
+    }
+
+    fn index_of(&self, key: &K) -> Option<usize> {
+        if !self.values.contains_key(key) {
+            return None;
+        }
+        return self.order.iter().position(|k| k == key);
+    }
+
+    /// Get an element by key.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        return self.values.get(key);
+    }
+
+    /// The keys of the elements, in order.
+    pub fn keys(&self) -> &[K] {
+        return &self.order;
+    }
+
+    /// Iterate the elements in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        return self.order.iter().map(|k| (k, self.values.get(k).unwrap()));
+    }
+
+    pub fn len(&self) -> usize {
+        return self.order.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.order.is_empty();
+    }
+}
+
+struct KeyedList_<K: Clone + Eq + Hash, V> {
+    mut_: RefCell<KeyedListMut_<K, V>>,
+}
+
+impl<K: Clone + Eq + Hash, V> ValueTrait for KeyedList_<K, V> {
+    fn next_links(&self) -> Vec<crate::Link> {
+        return self.mut_.borrow_mut().next();
+    }
+}
+
+impl<K: Clone + Eq + Hash, V> Cleanup for KeyedList_<K, V> {
+    fn clean(&self) {
+        self.mut_.borrow_mut().changes.clear();
+    }
+}
+
+/// A value that manages an ordered list of values, each identified by a unique
+/// key.  Changes are reported by key so they stay meaningful to consumers that
+/// reorder or filter the elements, and elements can be looked up by key in constant
+/// time.
+#[derive(Clone)]
+pub struct KeyedList<K: Clone + Eq + Hash, V>(Rc<KeyedList_<K, V>>);
+
+#[derive(Clone)]
+pub struct WeakKeyedList<K: Clone + Eq + Hash, V>(Weak<KeyedList_<K, V>>);
+
+impl<K: Clone + Eq + Hash + 'static, V: 'static> KeyedList<K, V> {
+    /// Create a new list.  If keys are duplicated, later elements replace earlier
+    /// elements.
+    pub fn new(initial: std::vec::Vec<(K, V)>) -> Self {
+        return Self::new_(initial, None);
+    }
+
+    /// Like `new`, but changes will also include the inserted, removed, and replaced
+    /// values (see `KeyedChange`) so consumers can set up and clean up resources
+    /// associated with them.  This clones values as they're changed.
+    pub fn new_keep_values(initial: std::vec::Vec<(K, V)>) -> Self where V: Clone {
+        return Self::new_(initial, Some(V::clone));
+    }
+
+    fn new_(initial: std::vec::Vec<(K, V)>, clone_value: Option<fn(&V) -> V>) -> Self {
+        let mut order = vec![];
+        let mut values = HashMap::new();
+        for (k, v) in initial {
+            if values.insert(k.clone(), v).is_none() {
+                order.push(k);
+            }
+        }
+        return KeyedList(Rc::new(KeyedList_ { mut_: RefCell::new(KeyedListMut_ {
+            order: order,
+            values: values,
+            changes: vec![],
+            clone_value: clone_value,
+            next: vec![],
+        }) }));
+    }
+
+    /// Used internally by the `link!` macro to establish graph edges between an input
+    /// value and the link.
+    pub fn add_next(&self, link: &Link) {
+        self.0.mut_.borrow_mut().next.push(Rc::downgrade(&link.0));
+    }
+
+    /// Get a weak reference to the list.
+    pub fn weak(&self) -> WeakKeyedList<K, V> {
+        return WeakKeyedList(Rc::downgrade(&self.0));
+    }
+
+    fn change_(&self, self2: &mut KeyedListMut_<K, V>, pc: &mut ProcessingContext, change: KeyedChange<K, V>) {
+        let first_change = self2.changes.is_empty();
+        self2.changes.push(change);
+        if first_change {
+            pc.1.cleanup.push(self.0.clone());
+            pc.1.mark_changed(self2.next());
+        }
+    }
+
+    fn move_(&self, self2: &mut KeyedListMut_<K, V>, pc: &mut ProcessingContext, from: usize, to: usize) {
+        if from == to {
+            return;
+        }
+        let key = self2.order.remove(from);
+        self2.order.insert(to, key.clone());
+        self.change_(self2, pc, KeyedChange::Move {
+            key: key,
+            from: from,
+            to: to,
+        });
+    }
+
+    /// Replace the value for `key` if it exists, returning the old value, otherwise
+    /// add it to the end of the list; triggers processing.
+    pub fn upsert(&self, pc: &mut ProcessingContext, key: K, value: V) -> Option<V> {
+        let mut self2 = self.0.mut_.borrow_mut();
+        let kept = self2.keep(&value);
+        let out = self2.values.insert(key.clone(), value);
+        if let Some(old) = &out {
+            let old = self2.keep(old);
+            self.change_(&mut self2, pc, KeyedChange::Update {
+                key: key,
+                value: kept,
+                old: old,
+            });
+        } else {
+            let index = self2.order.len();
+            self2.order.push(key.clone());
+            self.change_(&mut self2, pc, KeyedChange::Insert {
+                key: key,
+                index: index,
+                value: kept,
+            });
+        }
+        return out;
+    }
+
+    /// Like `upsert`, but the element ends up at `index` (moving it if it already
+    /// exists); triggers processing.  If the key exists, an `index` past the last
+    /// element moves it to the end.
+    ///
+    /// Panics if the key is new and `index` is greater than the length, before making
+    /// any changes.
+    pub fn insert(&self, pc: &mut ProcessingContext, index: usize, key: K, value: V) -> Option<V> {
+        let mut self2 = self.0.mut_.borrow_mut();
+        let from = self2.index_of(&key);
+        let len = self2.order.len();
+        if from.is_none() {
+            assert!(index <= len, "insertion index (is {}) should be <= len (is {})", index, len);
+        }
+        let kept = self2.keep(&value);
+        let out = self2.values.insert(key.clone(), value);
+        if let Some(from) = from {
+            let old = self2.keep(out.as_ref().unwrap());
+            self.change_(&mut self2, pc, KeyedChange::Update {
+                key: key,
+                value: kept,
+                old: old,
+            });
+            self.move_(&mut self2, pc, from, index.min(len - 1));
+        } else {
+            self2.order.insert(index, key.clone());
+            self.change_(&mut self2, pc, KeyedChange::Insert {
+                key: key,
+                index: index,
+                value: kept,
+            });
+        }
+        return out;
+    }
+
+    /// Remove the element with `key`, returning the value or None if there was no such
+    /// element; triggers processing.
+    pub fn remove_key(&self, pc: &mut ProcessingContext, key: &K) -> Option<V> {
+        let mut self2 = self.0.mut_.borrow_mut();
+        let index = self2.index_of(key)?;
+        let key = self2.order.remove(index);
+        let out = self2.values.remove(&key);
+        let old = out.as_ref().and_then(|v| self2.keep(v));
+        self.change_(&mut self2, pc, KeyedChange::Remove {
+            key: key,
+            index: index,
+            old: old,
+        });
+        return out;
+    }
+
+    /// Move the element with `key` so that it ends up at `to`; triggers processing.
+    /// Does nothing if there's no such element.  A `to` past the last element moves it
+    /// to the end.
+    pub fn move_key(&self, pc: &mut ProcessingContext, key: &K, to: usize) {
+        let mut self2 = self.0.mut_.borrow_mut();
+        let Some(from) = self2.index_of(key) else {
+            return;
+        };
+        let last = self2.order.len() - 1;
+        self.move_(&mut self2, pc, from, to.min(last));
+    }
+
+    /// Clears the collection; triggers processing.
+    pub fn clear(&self, pc: &mut ProcessingContext) {
+        let mut self2 = self.0.mut_.borrow_mut();
+        let mut values = std::mem::take(&mut self2.values);
+        let order = std::mem::take(&mut self2.order);
+        for (index, key) in order.into_iter().enumerate().rev() {
+            let old = if self2.clone_value.is_some() {
+                values.remove(&key)
+            } else {
+                None
+            };
+            self.change_(&mut self2, pc, KeyedChange::Remove {
+                key: key,
+                index: index,
+                old: old,
+            });
+        }
+    }
+
+    /// Get a copy of the value for `key`.
+    pub fn get(&self, key: &K) -> Option<V> where V: Clone {
+        return self.0.mut_.borrow().values.get(key).cloned();
+    }
+
+    /// The current state of the list.  A `Deref` wrapper with methods for looking up
+    /// and iterating elements.
+    ///
+    /// Borrows the list, must be released before calling other mutate methods.
+    pub fn borrow_values<'a>(&'a self) -> KeyedValuesRef<'a, K, V> {
+        return KeyedValuesRef(self.0.mut_.borrow());
+    }
+
+    /// Any changes during the current event handling that occurred to value to get it
+    /// to its current state.  A `Deref` wrapper around an internal `Vec`.  If you want
+    /// to iterate them, you'll need to call `.iter()` explicitly due to deref
+    /// limitations.
+    ///
+    /// Borrows the list, must be released before calling other mutate methods.
+    pub fn borrow_changes<'a>(&'a self) -> KeyedChangesRef<'a, K, V> {
+        return KeyedChangesRef(self.0.mut_.borrow());
+    }
+}
+
+impl<K: Clone + Eq + Hash + 'static, V: 'static> IntoValue for KeyedList<K, V> {
+    fn into_value(&self) -> Value {
+        return Value(self.0.clone());
+    }
+}
+
+impl<K: Clone + Eq + Hash + 'static, V: 'static> WeakKeyedList<K, V> {
+    pub fn upgrade(&self) -> Option<KeyedList<K, V>> {
+        return Some(KeyedList(self.0.upgrade()?));
+    }
+}
+
+pub struct KeyedValuesRef<'a, K: Clone + Eq + Hash + 'static, V: 'static>(Ref<'a, KeyedListMut_<K, V>>);
+
+impl<'a, K: Clone + Eq + Hash + 'static, V: 'static> Deref for KeyedValuesRef<'a, K, V> {
+    type Target = KeyedListMut_<K, V>;
+
+    fn deref(&self) -> &Self::Target {
+        return &self.0;
+    }
+}
+
+pub struct KeyedChangesRef<'a, K: Clone + Eq + Hash + 'static, V: 'static>(Ref<'a, KeyedListMut_<K, V>>);
+
+impl<'a, K: Clone + Eq + Hash + 'static, V: 'static> Deref for KeyedChangesRef<'a, K, V> {
+    type Target = Vec<KeyedChange<K, V>>;
+
+    fn deref(&self) -> &Self::Target {
+        return &self.0.changes;
+    }
+}
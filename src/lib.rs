@@ -1,7 +1,13 @@
 pub mod core;
 pub mod prim;
 pub mod list;
+pub mod keyed_list;
+pub mod map;
+pub mod set;
+pub mod index;
+pub mod tree;
 pub mod animate;
+pub mod easing;
 
 pub use crate::core::{
     Link,
@@ -16,9 +22,33 @@ pub use crate::prim::{
 pub use crate::list::{
     List,
 };
+pub use crate::keyed_list::{
+    KeyedList,
+};
+pub use crate::map::{
+    Map,
+};
+pub use crate::set::{
+    Set,
+};
+pub use crate::index::{
+    Index,
+};
+pub use crate::tree::{
+    Tree,
+    TreeNode,
+};
 pub use crate::animate::{
     Animator,
+    AnimationHandle,
+    AnimationState,
+    Animatable,
+    EaseExt,
     HistPrimEaseExt,
+    SpringExt,
+    SpringConfig,
+    Timeline,
+    Keyframe,
 };
 pub use paste;
 
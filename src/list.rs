@@ -4,10 +4,21 @@ use std::{
         Weak,
     },
     cell::{
+        Cell,
         RefCell,
         Ref,
     },
-    ops::Deref,
+    collections::{
+        HashMap,
+        VecDeque,
+    },
+    hash::Hash,
+    ops::{
+        Add,
+        Deref,
+        Range,
+        Sub,
+    },
 };
 use crate::{
     core::{
@@ -17,20 +28,125 @@ use crate::{
         Link_,
         IntoValue,
         Value,
+        LinkTrait,
     },
     Link,
+    Prim,
+    HistPrim,
+    prim::WeakHistPrim,
+    map::WeakMap,
+    index::WeakIndex,
+    Map,
+    Index,
 };
 
-pub struct Change<T: Clone> {
-    pub offset: usize,
-    pub remove: usize,
-    pub add: std::vec::Vec<T>,
+/// A single modification to a list.  Offsets in each change are relative to the
+/// state of the list after the previous change was applied.
+#[derive(Clone)]
+pub enum Change<T: Clone> {
+    /// `remove` elements were removed at `offset` and then `add` was inserted at the
+    /// same position.  `removed` has the removed elements if the list was created with
+    /// `new_keep_removed`.
+    Splice {
+        offset: usize,
+        remove: usize,
+        add: std::vec::Vec<T>,
+        removed: Option<std::vec::Vec<T>>,
+    },
+    /// The element at `offset` was replaced with `value`.  `old` has the replaced
+    /// element if the list was created with `new_keep_removed`.
+    Replace {
+        offset: usize,
+        value: T,
+        old: Option<T>,
+    },
+    /// The element at `from` was removed and reinserted so that it ends up at `to`
+    /// (as with `Vec::remove` followed by `Vec::insert`).
+    Move {
+        from: usize,
+        to: usize,
+    },
+}
+
+impl<T: Clone> Change<T> {
+    /// Apply this change to `sink`, converting added elements with `map`.  To mirror a
+    /// list, apply each change in order (as with `List::apply_changes`).
+    pub fn apply_to<U, S: ChangeSink<U> + ?Sized>(&self, sink: &mut S, mut map: impl FnMut(&T) -> U) {
+        match self {
+            Change::Splice { offset, remove, add, .. } => {
+                sink.splice(*offset, *remove, add.iter().map(&mut map).collect());
+            },
+            Change::Replace { offset, value, .. } => {
+                sink.replace(*offset, map(value));
+            },
+            Change::Move { from, to } => {
+                sink.move_item(*from, *to);
+            },
+        }
+    }
+}
+
+/// Implement this for containers (ex: UI widget children) that can mirror a `List`,
+/// see `Change::apply_to`.
+pub trait ChangeSink<U> {
+    /// Remove `remove` elements at `offset` and then insert `add` at `offset`.
+    fn splice(&mut self, offset: usize, remove: usize, add: std::vec::Vec<U>);
+
+    /// Replace the element at `offset`.  By default this is a splice.
+    fn replace(&mut self, offset: usize, value: U) {
+        self.splice(offset, 1, vec![value]);
+    }
+
+    /// Remove the element at `from` and reinsert it so it ends up at `to`.
+    fn move_item(&mut self, from: usize, to: usize);
+}
+
+impl<U> ChangeSink<U> for std::vec::Vec<U> {
+    fn splice(&mut self, offset: usize, remove: usize, add: std::vec::Vec<U>) {
+        std::vec::Vec::splice(self, offset .. offset + remove, add);
+    }
+
+    fn replace(&mut self, offset: usize, value: U) {
+        self[offset] = value;
+    }
+
+    fn move_item(&mut self, from: usize, to: usize) {
+        assert!(to < self.len(), "move destination (is {}) should be < len (is {})", to, self.len());
+        let value = self.remove(from);
+        self.insert(to, value);
+    }
+}
+
+impl<U> ChangeSink<U> for VecDeque<U> {
+    fn splice(&mut self, offset: usize, remove: usize, add: std::vec::Vec<U>) {
+        self.drain(offset .. offset + remove);
+        for (i, value) in add.into_iter().enumerate() {
+            self.insert(offset + i, value);
+        }
+    }
+
+    fn replace(&mut self, offset: usize, value: U) {
+        self[offset] = value;
+    }
+
+    fn move_item(&mut self, from: usize, to: usize) {
+        assert!(to < self.len(), "move destination (is {}) should be < len (is {})", to, self.len());
+        let value = self.remove(from).unwrap();
+        self.insert(to, value);
+    }
 }
 
 pub struct ListMut_<T: Clone> {
     value: std::vec::Vec<T>,
     changes: std::vec::Vec<Change<T>>,
+    keep_removed: bool,
+    /// Elements are removed from the front when the list gets longer than this.
+    max_len: Option<usize>,
     next: std::vec::Vec<Weak<Link_>>,
+    /// For derived lists, the links that maintain this list.  They're owned by the
+    /// list so they stay alive as long as the list does.
+    links: std::vec::Vec<Link>,
+    cursors: std::vec::Vec<Weak<RefCell<ListCursor_<T>>>>,
 }
 
 impl<T: Clone> ListMut_<T> {
@@ -76,25 +192,71 @@ pub struct List<T: Clone>(Rc<List_<T>>);
 pub struct WeakList<T: Clone>(Weak<List_<T>>);
 
 impl<T: Clone + 'static> List<T> {
-    pub fn new(initial: std::vec::Vec<T>) -> Self {
+    fn new_(initial: std::vec::Vec<T>, keep_removed: bool, max_len: Option<usize>) -> Self {
         return List(Rc::new(List_ { mut_: RefCell::new(ListMut_ {
             value: initial,
             changes: vec![],
+            keep_removed: keep_removed,
+            max_len: max_len,
             next: vec![],
+            links: vec![],
+            cursors: vec![],
         }) }));
     }
 
+    pub fn new(initial: std::vec::Vec<T>) -> Self {
+        return Self::new_(initial, false, None);
+    }
+
+    /// Like `new`, but changes will also include the removed or replaced elements (see
+    /// `Change`) so consumers can clean up resources associated with them.  This
+    /// clones every element removed from the list.
+    pub fn new_keep_removed(initial: std::vec::Vec<T>) -> Self {
+        return Self::new_(initial, true, None);
+    }
+
+    /// Like `new`, but the list will never be longer than `max_len` (ex: for logs).
+    /// When elements are added past the limit, elements are removed from the front to
+    /// make room, recorded as a separate change after the addition.
+    pub fn new_bounded(mut initial: std::vec::Vec<T>, max_len: usize) -> Self {
+        let excess = initial.len().saturating_sub(max_len);
+        initial.drain(0 .. excess);
+        return Self::new_(initial, false, Some(max_len));
+    }
+
     /// Used internally by the `link!` macro to establish graph edges between an input
     /// value and the link.
     pub fn add_next(&self, link: &Link) {
         self.0.mut_.borrow_mut().next.push(Rc::downgrade(&link.0));
     }
 
+    /// Undo `add_next`.
+    fn remove_next(&self, link: &Link) {
+        let link = Rc::downgrade(&link.0);
+        self.0.mut_.borrow_mut().next.retain(|e| !e.ptr_eq(&link));
+    }
+
     /// Get a weak reference to the list.
     pub fn weak(&self) -> WeakList<T> {
         return WeakList(Rc::downgrade(&self.0));
     }
 
+    fn change_(&self, self2: &mut ListMut_<T>, pc: &mut ProcessingContext, change: Change<T>) {
+        let first_change = self2.changes.is_empty();
+        self2.cursors.retain(|c| {
+            let Some(c) = c.upgrade() else {
+                return false;
+            };
+            c.borrow_mut().push(&change);
+            return true;
+        });
+        self2.changes.push(change);
+        if first_change {
+            pc.1.cleanup.push(self.0.clone());
+            pc.1.mark_changed(self2.next());
+        }
+    }
+
     fn splice_(
         &self,
         self2: &mut ListMut_<T>,
@@ -106,24 +268,63 @@ impl<T: Clone + 'static> List<T> {
         if remove == 0 && add.is_empty() {
             return vec![];
         }
-        let first_change = self2.changes.is_empty();
-        let out = self2.value.splice(offset .. offset + remove, add.clone()).collect();
-        self2.changes.push(Change {
+        let out: std::vec::Vec<T> = self2.value.splice(offset .. offset + remove, add.clone()).collect();
+        let removed = if self2.keep_removed {
+            Some(out.clone())
+        } else {
+            None
+        };
+        self.change_(self2, pc, Change::Splice {
             offset: offset,
             remove: remove,
             add: add,
+            removed: removed,
         });
-        if first_change {
-            pc.1.cleanup.push(self.0.clone());
-            if !pc.1.processing {
-                for l in self2.next() {
-                    pc.1.step1_stacked_links.push((true, l));
-                }
+        return out;
+    }
+
+    /// For bounded lists, remove elements from the front past `max_len`.  Called once
+    /// at the end of each public method that can grow the list, so that multi-step
+    /// edits use consistent offsets.
+    fn trim_(&self, self2: &mut ListMut_<T>, pc: &mut ProcessingContext) {
+        if let Some(max_len) = self2.max_len {
+            let excess = self2.value.len().saturating_sub(max_len);
+            if excess > 0 {
+                self.splice_(self2, pc, 0, excess, vec![]);
             }
         }
+    }
+
+    fn set_(&self, self2: &mut ListMut_<T>, pc: &mut ProcessingContext, index: usize, value: T) -> T {
+        let out = std::mem::replace(&mut self2.value[index], value.clone());
+        let old = if self2.keep_removed {
+            Some(out.clone())
+        } else {
+            None
+        };
+        self.change_(self2, pc, Change::Replace {
+            offset: index,
+            value: value,
+            old: old,
+        });
         return out;
     }
 
+    fn move_(&self, self2: &mut ListMut_<T>, pc: &mut ProcessingContext, from: usize, to: usize) {
+        let len = self2.value.len();
+        assert!(from < len, "move source (is {}) should be < len (is {})", from, len);
+        assert!(to < len, "move destination (is {}) should be < len (is {})", to, len);
+        if from == to {
+            return;
+        }
+        let value = self2.value.remove(from);
+        self2.value.insert(to, value);
+        self.change_(self2, pc, Change::Move {
+            from: from,
+            to: to,
+        });
+    }
+
     /// Modify the value; triggers processing.
     pub fn splice(
         &self,
@@ -133,7 +334,9 @@ impl<T: Clone + 'static> List<T> {
         add: std::vec::Vec<T>,
     ) -> std::vec::Vec<T> {
         let mut self2 = self.0.mut_.borrow_mut();
-        return self.splice_(&mut self2, pc, offset, remove, add);
+        let out = self.splice_(&mut self2, pc, offset, remove, add);
+        self.trim_(&mut self2, pc);
+        return out;
     }
 
     /// Add one element; triggers processing.
@@ -141,6 +344,7 @@ impl<T: Clone + 'static> List<T> {
         let mut self2 = self.0.mut_.borrow_mut();
         let len = self2.value.len();
         self.splice_(&mut self2, pc, len, 0, vec![value]);
+        self.trim_(&mut self2, pc);
     }
 
     /// Remove one element, return the element or None if the list was empty; triggers
@@ -159,6 +363,50 @@ impl<T: Clone + 'static> List<T> {
         let mut self2 = self.0.mut_.borrow_mut();
         let len = self2.value.len();
         self.splice_(&mut self2, pc, len, 0, values);
+        self.trim_(&mut self2, pc);
+    }
+
+    /// Insert one element at `index`, shifting later elements; triggers processing.
+    pub fn insert(&self, pc: &mut ProcessingContext, index: usize, value: T) {
+        let mut self2 = self.0.mut_.borrow_mut();
+        self.splice_(&mut self2, pc, index, 0, vec![value]);
+        self.trim_(&mut self2, pc);
+    }
+
+    /// Remove and return the element at `index`, shifting later elements; triggers
+    /// processing.
+    pub fn remove(&self, pc: &mut ProcessingContext, index: usize) -> T {
+        let mut self2 = self.0.mut_.borrow_mut();
+        return self.splice_(&mut self2, pc, index, 1, vec![]).into_iter().next().unwrap();
+    }
+
+    /// Replace the element at `index`, returning the old element; triggers processing.
+    pub fn set(&self, pc: &mut ProcessingContext, index: usize, value: T) -> T {
+        let mut self2 = self.0.mut_.borrow_mut();
+        return self.set_(&mut self2, pc, index, value);
+    }
+
+    /// Exchange the elements at `a` and `b`.  This is recorded as moves so consumers
+    /// can reuse existing resources for the elements.  Triggers processing.
+    pub fn swap(&self, pc: &mut ProcessingContext, a: usize, b: usize) {
+        let (a, b) = if a < b {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        if a == b {
+            return;
+        }
+        let mut self2 = self.0.mut_.borrow_mut();
+        self.move_(&mut self2, pc, b, a);
+        self.move_(&mut self2, pc, a + 1, b);
+    }
+
+    /// Move the element at `from` so that it ends up at `to`, shifting the elements in
+    /// between; triggers processing.
+    pub fn move_item(&self, pc: &mut ProcessingContext, from: usize, to: usize) {
+        let mut self2 = self.0.mut_.borrow_mut();
+        self.move_(&mut self2, pc, from, to);
     }
 
     /// Clears the collection; triggers processing.
@@ -177,6 +425,967 @@ impl<T: Clone + 'static> List<T> {
         }
     }
 
+    /// Replace the contents of the list with `values`, recording a minimal sequence of
+    /// changes rather than clearing it.  Elements are matched by the key returned by
+    /// `key`, and matched elements for which `eq` returns false are updated with a
+    /// `Change::Replace`.  Use `replace_all` if elements implement `PartialEq`.
+    /// Triggers processing if anything changed.
+    pub fn replace_all_by<K: PartialEq>(
+        &self,
+        pc: &mut ProcessingContext,
+        values: std::vec::Vec<T>,
+        key: impl Fn(&T) -> K,
+        eq: impl Fn(&T, &T) -> bool,
+    ) {
+        let mut self2 = self.0.mut_.borrow_mut();
+        let ops = diff(&self2.value, &values, |a, b| key(a) == key(b));
+        self.apply_diff_(&mut self2, pc, ops, values, eq);
+        self.trim_(&mut self2, pc);
+    }
+
+    /// Modify a copy of the elements with direct `Vec` access (ex: `retain`, `sort`,
+    /// `dedup`), then replace the contents with the result in a single splice.  Use
+    /// `mutate` if elements implement `PartialEq` to record only the elements that
+    /// changed.  Triggers processing unless the list was and remains empty.
+    pub fn mutate_replace<R>(&self, pc: &mut ProcessingContext, f: impl FnOnce(&mut std::vec::Vec<T>) -> R) -> R {
+        let mut self2 = self.0.mut_.borrow_mut();
+        let mut values = self2.value.clone();
+        let out = f(&mut values);
+        let len = self2.value.len();
+        if len > 0 || !values.is_empty() {
+            self.splice_(&mut self2, pc, 0, len, values);
+        }
+        self.trim_(&mut self2, pc);
+        return out;
+    }
+
+    fn apply_diff_(
+        &self,
+        self2: &mut ListMut_<T>,
+        pc: &mut ProcessingContext,
+        ops: std::vec::Vec<DiffOp>,
+        values: std::vec::Vec<T>,
+        eq: impl Fn(&T, &T) -> bool,
+    ) {
+        let mut values = values.into_iter().map(Some).collect::<std::vec::Vec<_>>();
+        let mut at = 0;
+        let mut remove = 0;
+        let mut add = vec![];
+        for op in ops {
+            match op {
+                DiffOp::Equal(new_i) => {
+                    if remove > 0 || !add.is_empty() {
+                        let add_len = add.len();
+                        self.splice_(self2, pc, at, remove, add);
+                        at += add_len;
+                        remove = 0;
+                        add = vec![];
+                    }
+                    let value = values[new_i].take().unwrap();
+                    if !eq(&self2.value[at], &value) {
+                        self.set_(self2, pc, at, value);
+                    }
+                    at += 1;
+                },
+                DiffOp::Delete => {
+                    remove += 1;
+                },
+                DiffOp::Insert(new_i) => {
+                    add.push(values[new_i].take().unwrap());
+                },
+            }
+        }
+        if remove > 0 || !add.is_empty() {
+            self.splice_(self2, pc, at, remove, add);
+        }
+    }
+
+    /// Create a new list derived from this one, with `f` applied to each element. The
+    /// derived list is updated incrementally - `f` is only called for new elements.
+    /// The derived list keeps itself updated as long as it exists and shouldn't be
+    /// modified directly.
+    pub fn map<U: Clone + 'static>(&self, pc: &mut ProcessingContext, f: impl Fn(&T) -> U + 'static) -> List<U> {
+        struct MapLink<T: Clone, U: Clone> {
+            source: List<T>,
+            skip: Cell<usize>,
+            dest: WeakList<U>,
+            f: Box<dyn Fn(&T) -> U>,
+        }
+
+        impl<T: Clone + 'static, U: Clone + 'static> LinkTrait for MapLink<T, U> {
+            fn call(&self, pc: &mut ProcessingContext) {
+                let Some(dest) = self.dest.upgrade() else {
+                    return;
+                };
+                let changes = self.source.borrow_changes();
+                for change in changes.iter().skip(self.skip.replace(0)) {
+                    match change {
+                        Change::Splice { offset, remove, add, .. } => {
+                            dest.splice(pc, *offset, *remove, add.iter().map(&self.f).collect());
+                        },
+                        Change::Replace { offset, value, .. } => {
+                            dest.set(pc, *offset, (self.f)(value));
+                        },
+                        Change::Move { from, to } => {
+                            dest.move_item(pc, *from, *to);
+                        },
+                    }
+                }
+            }
+
+            fn next_values(&self) -> Vec<Value> {
+                return self.dest.upgrade().map(|d| d.into_value()).into_iter().collect();
+            }
+        }
+
+        let (dest, skip) = {
+            let self2 = self.0.mut_.borrow();
+            (List::new(self2.value.iter().map(&f).collect()), self2.changes.len())
+        };
+        let link = Link::new(pc, MapLink {
+            source: self.clone(),
+            skip: Cell::new(skip),
+            dest: dest.weak(),
+            f: Box::new(f),
+        });
+        self.add_next(&link);
+        dest.0.mut_.borrow_mut().links.push(link);
+        return dest;
+    }
+
+    /// Create a new list derived from this one with only the elements for which
+    /// `predicate` returns true.  The derived list is updated incrementally -
+    /// `predicate` is only called for new elements.  The derived list keeps itself
+    /// updated as long as it exists and shouldn't be modified directly.
+    pub fn filter(&self, pc: &mut ProcessingContext, predicate: impl Fn(&T) -> bool + 'static) -> List<T> {
+        return self.filter_(pc, Prim::new(()), Box::new(move |_, v| predicate(v)));
+    }
+
+    /// Like `filter`, but `predicate` also depends on the value of `param` (ex: search
+    /// text).  When `param` changes, all elements are reevaluated once, otherwise
+    /// updates are incremental.
+    pub fn filter_with<P: 'static>(
+        &self,
+        pc: &mut ProcessingContext,
+        param: &Prim<P>,
+        predicate: impl Fn(&P, &T) -> bool + 'static,
+    ) -> List<T> {
+        return self.filter_(pc, Prim(param.0.clone()), Box::new(predicate));
+    }
+
+    fn filter_<P: 'static>(
+        &self,
+        pc: &mut ProcessingContext,
+        param: Prim<P>,
+        predicate: FilterFn<P, T>,
+    ) -> List<T> {
+        struct FilterLink<T: Clone, P> {
+            source: List<T>,
+            param: Prim<P>,
+            skip: Cell<usize>,
+            /// Whether each element of the source is in the output
+            included: RefCell<std::vec::Vec<bool>>,
+            dest: WeakList<T>,
+            f: FilterFn<P, T>,
+        }
+
+        impl<T: Clone + 'static, P: 'static> LinkTrait for FilterLink<T, P> {
+            fn call(&self, pc: &mut ProcessingContext) {
+                let Some(dest) = self.dest.upgrade() else {
+                    return;
+                };
+                let param = self.param.borrow();
+                let mut included = self.included.borrow_mut();
+                let dest_index = |included: &[bool], index: usize| included[.. index].iter().filter(|x| **x).count();
+                let changes = self.source.borrow_changes();
+                for change in changes.iter().skip(self.skip.replace(0)) {
+                    match change {
+                        Change::Splice { offset, remove, add, .. } => {
+                            let dest_offset = dest_index(&included, *offset);
+                            let dest_remove =
+                                included[*offset .. *offset + *remove].iter().filter(|x| **x).count();
+                            let add_included = add.iter().map(|v| (self.f)(&param, v)).collect::<Vec<_>>();
+                            let dest_add =
+                                add
+                                    .iter()
+                                    .zip(add_included.iter())
+                                    .filter(|(_, i)| **i)
+                                    .map(|(v, _)| v.clone())
+                                    .collect();
+                            included.splice(*offset .. *offset + *remove, add_included);
+                            dest.splice(pc, dest_offset, dest_remove, dest_add);
+                        },
+                        Change::Replace { offset, value, .. } => {
+                            let dest_offset = dest_index(&included, *offset);
+                            let now = (self.f)(&param, value);
+                            match (included[*offset], now) {
+                                (true, true) => {
+                                    dest.set(pc, dest_offset, value.clone());
+                                },
+                                (true, false) => {
+                                    dest.remove(pc, dest_offset);
+                                },
+                                (false, true) => {
+                                    dest.insert(pc, dest_offset, value.clone());
+                                },
+                                (false, false) => { },
+                            }
+                            included[*offset] = now;
+                        },
+                        Change::Move { from, to } => {
+                            let dest_from = dest_index(&included, *from);
+                            let i = included.remove(*from);
+                            included.insert(*to, i);
+                            if i {
+                                dest.move_item(pc, dest_from, dest_index(&included, *to));
+                            }
+                        },
+                    }
+                }
+                drop(changes);
+                if self.param.changed() {
+                    // Reevaluate everything, removing and adding contiguous runs of elements
+                    let values = self.source.borrow_values();
+                    let mut at = 0;
+                    let mut remove = 0;
+                    let mut add = vec![];
+                    for (v, i) in values.iter().zip(included.iter_mut()) {
+                        let now = (self.f)(&param, v);
+                        match (*i, now) {
+                            (true, true) | (false, false) => {
+                                if remove > 0 || !add.is_empty() {
+                                    let add_len = add.len();
+                                    dest.splice(pc, at, remove, add);
+                                    at += add_len;
+                                    remove = 0;
+                                    add = vec![];
+                                }
+                                if now {
+                                    at += 1;
+                                }
+                            },
+                            (true, false) => {
+                                remove += 1;
+                            },
+                            (false, true) => {
+                                add.push(v.clone());
+                            },
+                        }
+                        *i = now;
+                    }
+                    if remove > 0 || !add.is_empty() {
+                        dest.splice(pc, at, remove, add);
+                    }
+                }
+            }
+
+            fn next_values(&self) -> Vec<Value> {
+                return self.dest.upgrade().map(|d| d.into_value()).into_iter().collect();
+            }
+        }
+
+        let (dest, included, skip) = {
+            let self2 = self.0.mut_.borrow();
+            let param = param.borrow();
+            let included = self2.value.iter().map(|v| predicate(&param, v)).collect::<Vec<_>>();
+            let dest =
+                List::new(
+                    self2
+                        .value
+                        .iter()
+                        .zip(included.iter())
+                        .filter(|(_, i)| **i)
+                        .map(|(v, _)| v.clone())
+                        .collect(),
+                );
+            (dest, included, self2.changes.len())
+        };
+        let link = Link::new(pc, FilterLink {
+            source: self.clone(),
+            param: Prim(param.0.clone()),
+            skip: Cell::new(skip),
+            included: RefCell::new(included),
+            dest: dest.weak(),
+            f: predicate,
+        });
+        self.add_next(&link);
+        param.add_next(&link);
+        dest.0.mut_.borrow_mut().links.push(link);
+        return dest;
+    }
+
+    /// Create a new list derived from this one, with the elements sorted by `cmp`.
+    /// Elements that compare equal are kept in the order they were added.  The derived
+    /// list is updated incrementally, with each added element placed via a binary
+    /// search.  The derived list keeps itself updated as long as it exists and
+    /// shouldn't be modified directly.
+    pub fn sorted_by(
+        &self,
+        pc: &mut ProcessingContext,
+        cmp: impl Fn(&T, &T) -> std::cmp::Ordering + 'static,
+    ) -> List<T> {
+        struct SortLink<T: Clone> {
+            source: List<T>,
+            skip: Cell<usize>,
+            /// The index in the output of each element of the source
+            dest_of: RefCell<std::vec::Vec<usize>>,
+            dest: WeakList<T>,
+            cmp: CmpFn<T>,
+        }
+
+        impl<T: Clone + 'static> SortLink<T> {
+            /// The index after any equal elements in `dest`, excluding the element at
+            /// `exclude` (the result is the index in `dest` as if that element were
+            /// removed).
+            fn upper_bound(&self, dest: &[T], value: &T, exclude: Option<usize>) -> usize {
+                let len = dest.len() - exclude.map(|_| 1).unwrap_or(0);
+                let mut low = 0;
+                let mut high = len;
+                while low < high {
+                    let mid = (low + high) / 2;
+                    let mid_dest = match exclude {
+                        Some(exclude) if mid >= exclude => mid + 1,
+                        _ => mid,
+                    };
+                    if (self.cmp)(&dest[mid_dest], value) == std::cmp::Ordering::Greater {
+                        high = mid;
+                    } else {
+                        low = mid + 1;
+                    }
+                }
+                return low;
+            }
+        }
+
+        impl<T: Clone + 'static> LinkTrait for SortLink<T> {
+            fn call(&self, pc: &mut ProcessingContext) {
+                let Some(dest) = self.dest.upgrade() else {
+                    return;
+                };
+                let mut dest_of = self.dest_of.borrow_mut();
+                let changes = self.source.borrow_changes();
+                for change in changes.iter().skip(self.skip.replace(0)) {
+                    match change {
+                        Change::Splice { offset, remove, add, .. } => {
+                            for _ in 0 .. *remove {
+                                let removed = dest_of.remove(*offset);
+                                for d in dest_of.iter_mut() {
+                                    if *d > removed {
+                                        *d -= 1;
+                                    }
+                                }
+                                dest.remove(pc, removed);
+                            }
+                            for (i, value) in add.iter().enumerate() {
+                                let at = self.upper_bound(&dest.borrow_values(), value, None);
+                                for d in dest_of.iter_mut() {
+                                    if *d >= at {
+                                        *d += 1;
+                                    }
+                                }
+                                dest_of.insert(*offset + i, at);
+                                dest.insert(pc, at, value.clone());
+                            }
+                        },
+                        Change::Replace { offset, value, .. } => {
+                            let from = dest_of[*offset];
+                            let to = self.upper_bound(&dest.borrow_values(), value, Some(from));
+                            for d in dest_of.iter_mut() {
+                                if from < to && *d > from && *d <= to {
+                                    *d -= 1;
+                                } else if to < from && *d >= to && *d < from {
+                                    *d += 1;
+                                }
+                            }
+                            dest_of[*offset] = to;
+                            dest.set(pc, from, value.clone());
+                            dest.move_item(pc, from, to);
+                        },
+                        Change::Move { from, to } => {
+                            // Order in the source doesn't affect the output
+                            let d = dest_of.remove(*from);
+                            dest_of.insert(*to, d);
+                        },
+                    }
+                }
+            }
+
+            fn next_values(&self) -> Vec<Value> {
+                return self.dest.upgrade().map(|d| d.into_value()).into_iter().collect();
+            }
+        }
+
+        let (dest, dest_of, skip) = {
+            let self2 = self.0.mut_.borrow();
+            let mut order = (0 .. self2.value.len()).collect::<Vec<_>>();
+            order.sort_by(|a, b| cmp(&self2.value[*a], &self2.value[*b]));
+            let mut dest_of = vec![0; order.len()];
+            for (dest_i, source_i) in order.iter().enumerate() {
+                dest_of[*source_i] = dest_i;
+            }
+            let dest = List::new(order.iter().map(|i| self2.value[*i].clone()).collect());
+            (dest, dest_of, self2.changes.len())
+        };
+        let link = Link::new(pc, SortLink {
+            source: self.clone(),
+            skip: Cell::new(skip),
+            dest_of: RefCell::new(dest_of),
+            dest: dest.weak(),
+            cmp: Box::new(cmp),
+        });
+        self.add_next(&link);
+        dest.0.mut_.borrow_mut().links.push(link);
+        return dest;
+    }
+
+    /// Create a new list derived from this one, with the elements in `range` (ex: the
+    /// visible rows of a virtual scroll view).  The derived list only changes when
+    /// elements at positions in the window change, so links using it only fire then.
+    /// Its changes are in window coordinates.  Note that insertions and removals
+    /// before the window shift elements into or out of it.  `range` can be changed at
+    /// any time.  The derived list keeps itself updated as long as it exists and
+    /// shouldn't be modified directly.
+    ///
+    /// Updates take time proportional to the window size and the number of changes,
+    /// not the length of this list.
+    pub fn window(&self, pc: &mut ProcessingContext, range: &Prim<Range<usize>>) -> List<T> {
+        /// An element of the window while changes are being processed
+        #[derive(Clone, Copy)]
+        enum Slot {
+            /// The element at this index of the window before the event, and whether
+            /// it was replaced
+            Old(usize, bool),
+            /// An element that entered the window during the event, its value is
+            /// taken from the final state of the source
+            New,
+        }
+
+        struct WindowLink<T: Clone> {
+            source: List<T>,
+            range: Prim<Range<usize>>,
+            skip: Cell<usize>,
+            /// The length of the source as of the last call
+            len: Cell<usize>,
+            current_range: RefCell<Range<usize>>,
+            dest: WeakList<T>,
+        }
+
+        /// Update `slots` after a splice `(offset, remove, add_len)` of the source,
+        /// which is `len` long afterwards.
+        fn splice_slots(
+            slots: &mut std::vec::Vec<Slot>,
+            len: usize,
+            range: &Range<usize>,
+            (offset, remove, add_len): (usize, usize, usize),
+        ) {
+            let width = range.end.saturating_sub(range.start);
+            let start = range.start as isize;
+            let offset = offset as isize;
+            let delta = add_len as isize - remove as isize;
+
+            // In coordinates relative to the window start, ignoring the window end,
+            // the change replaces `keep_end .. suffix_start` with the new elements in
+            // the source between the two
+            let keep_end = offset.max(start) - start;
+            let suffix_start = keep_end.max(offset + remove as isize - start).max(-delta);
+            let window_len = slots.len();
+            let keep_end = (keep_end as usize).min(window_len);
+            let mut suffix_start = (suffix_start as usize).min(window_len);
+            let add_start = range.start + keep_end;
+            let mut add_end = (start + suffix_start as isize + delta).max(add_start as isize) as usize;
+            if add_end > range.start + width {
+                // The kept suffix is pushed out of the window
+                add_end = range.start + width;
+                suffix_start = window_len;
+            }
+            let add = add_end.min(len).saturating_sub(add_start);
+            slots.splice(keep_end .. suffix_start, (0 .. add).map(|_| Slot::New));
+
+            // Fix up the window end
+            let want_len = len.saturating_sub(range.start).min(width);
+            slots.resize(want_len, Slot::New);
+        }
+
+        impl<T: Clone + 'static> LinkTrait for WindowLink<T> {
+            fn call(&self, pc: &mut ProcessingContext) {
+                let Some(dest) = self.dest.upgrade() else {
+                    return;
+                };
+                let mut current_range = self.current_range.borrow_mut();
+                let old_len = dest.borrow_values().len();
+                let mut slots = (0 .. old_len).map(|i| Slot::Old(i, false)).collect::<std::vec::Vec<_>>();
+                let mut len = self.len.get();
+                let changes = self.source.borrow_changes();
+                for change in changes.iter().skip(self.skip.replace(0)) {
+                    match change {
+                        Change::Splice { offset, remove, add, .. } => {
+                            len = len + add.len() - *remove;
+                            splice_slots(&mut slots, len, &current_range, (*offset, *remove, add.len()));
+                        },
+                        Change::Replace { offset, .. } => {
+                            if let Some(Slot::Old(_, replaced)) = offset.checked_sub(current_range.start).and_then(|i| slots.get_mut(i)) {
+                                *replaced = true;
+                            }
+                        },
+                        Change::Move { from, to } => {
+                            splice_slots(&mut slots, len - 1, &current_range, (*from, 1, 0));
+                            splice_slots(&mut slots, len, &current_range, (*to, 0, 1));
+                        },
+                    }
+                }
+                drop(changes);
+                self.len.set(len);
+                let range = self.range.borrow().clone();
+                if range != *current_range {
+                    let new_end = range.end.min(len).max(range.start);
+                    slots = (range.start .. new_end).map(|p| {
+                        return p.checked_sub(current_range.start).and_then(|i| slots.get(i).copied()).unwrap_or(Slot::New);
+                    }).collect();
+                    *current_range = range;
+                }
+
+                // Apply the difference between the old window and the slots, taking new
+                // and replaced elements from the final state of the source
+                let source = self.source.borrow_values();
+                let values = source.get(current_range.start ..).unwrap_or_default();
+                let old = (0 .. old_len).map(|i| Slot::Old(i, false)).collect::<std::vec::Vec<_>>();
+                let ops = diff(&old, &slots, |a, b| match (a, b) {
+                    (Slot::Old(a, _), Slot::Old(b, _)) => a == b,
+                    _ => false,
+                });
+                let mut at = 0;
+                let mut remove = 0;
+                let mut add = vec![];
+                for op in ops {
+                    match op {
+                        DiffOp::Equal(new_i) => {
+                            if remove > 0 || !add.is_empty() {
+                                let add_len = add.len();
+                                dest.splice(pc, at, remove, add);
+                                at += add_len;
+                                remove = 0;
+                                add = vec![];
+                            }
+                            if let Slot::Old(_, true) = slots[new_i] {
+                                dest.set(pc, at, values[new_i].clone());
+                            }
+                            at += 1;
+                        },
+                        DiffOp::Delete => {
+                            remove += 1;
+                        },
+                        DiffOp::Insert(new_i) => {
+                            add.push(values[new_i].clone());
+                        },
+                    }
+                }
+                if remove > 0 || !add.is_empty() {
+                    dest.splice(pc, at, remove, add);
+                }
+            }
+
+            fn next_values(&self) -> Vec<Value> {
+                return self.dest.upgrade().map(|d| d.into_value()).into_iter().collect();
+            }
+        }
+
+        let (dest, len, current_range, skip) = {
+            let self2 = self.0.mut_.borrow();
+            let current_range = range.borrow().clone();
+            let end = current_range.end.min(self2.value.len()).max(current_range.start);
+            let dest = List::new(self2.value.get(current_range.start .. end).unwrap_or_default().to_vec());
+            (dest, self2.value.len(), current_range, self2.changes.len())
+        };
+        let link = Link::new(pc, WindowLink {
+            source: self.clone(),
+            range: range.clone(),
+            skip: Cell::new(skip),
+            len: Cell::new(len),
+            current_range: RefCell::new(current_range),
+            dest: dest.weak(),
+        });
+        self.add_next(&link);
+        range.add_next(&link);
+        dest.0.mut_.borrow_mut().links.push(link);
+        return dest;
+    }
+
+    /// Create a map derived from this list, grouping elements by the key returned by
+    /// `key`.  Each group is a list of the elements with that key in the same order as
+    /// in this list, and is updated incrementally.  Groups are added to the map when
+    /// their first element appears and removed when they become empty, so the map's
+    /// `inserted` and `removed` changes can be used to add and remove section headers.
+    /// The map keeps itself updated as long as it exists and shouldn't be modified
+    /// directly.
+    ///
+    /// `key` is called once per new element.
+    pub fn group_by<K: Clone + Eq + Hash + 'static>(
+        &self,
+        pc: &mut ProcessingContext,
+        key: impl Fn(&T) -> K + 'static,
+    ) -> Map<K, List<T>> {
+        struct GroupLink<K: Clone + Eq + Hash, T: Clone> {
+            source: List<T>,
+            skip: Cell<usize>,
+            /// The key of each element of `source`
+            keys: RefCell<std::vec::Vec<K>>,
+            dest: WeakMap<K, List<T>>,
+            key: Box<dyn Fn(&T) -> K>,
+        }
+
+        impl<K: Clone + Eq + Hash + 'static, T: Clone + 'static> GroupLink<K, T> {
+            /// The index within the group of the element at `index` in the source
+            fn group_index(keys: &[K], index: usize, key: &K) -> usize {
+                return keys[.. index].iter().filter(|k| *k == key).count();
+            }
+
+            fn remove(&self, pc: &mut ProcessingContext, dest: &Map<K, List<T>>, keys: &mut Vec<K>, index: usize) {
+                let key = keys.remove(index);
+                let group = dest.get(&key).unwrap();
+                group.remove(pc, Self::group_index(keys, index, &key));
+            }
+
+            fn insert(
+                &self,
+                pc: &mut ProcessingContext,
+                dest: &Map<K, List<T>>,
+                keys: &mut Vec<K>,
+                index: usize,
+                value: T,
+            ) {
+                let key = (self.key)(&value);
+                let group_index = Self::group_index(keys, index, &key);
+                keys.insert(index, key.clone());
+                match dest.get(&key) {
+                    Some(group) => group.insert(pc, group_index, value),
+                    None => {
+                        dest.insert(pc, key, List::new(vec![value]));
+                    },
+                }
+            }
+        }
+
+        impl<K: Clone + Eq + Hash + 'static, T: Clone + 'static> LinkTrait for GroupLink<K, T> {
+            fn call(&self, pc: &mut ProcessingContext) {
+                let Some(dest) = self.dest.upgrade() else {
+                    return;
+                };
+                let mut keys = self.keys.borrow_mut();
+                let changes = self.source.borrow_changes();
+                for change in changes.iter().skip(self.skip.replace(0)) {
+                    match change {
+                        Change::Splice { offset, remove, add, .. } => {
+                            for _ in 0 .. *remove {
+                                self.remove(pc, &dest, &mut keys, *offset);
+                            }
+                            for (i, value) in add.iter().enumerate() {
+                                self.insert(pc, &dest, &mut keys, *offset + i, value.clone());
+                            }
+                        },
+                        Change::Replace { offset, value, .. } => {
+                            let key = (self.key)(value);
+                            if key == keys[*offset] {
+                                let group_index = Self::group_index(&keys, *offset, &key);
+                                dest.get(&key).unwrap().set(pc, group_index, value.clone());
+                            } else {
+                                self.remove(pc, &dest, &mut keys, *offset);
+                                self.insert(pc, &dest, &mut keys, *offset, value.clone());
+                            }
+                        },
+                        Change::Move { from, to } => {
+                            let key = keys.remove(*from);
+                            let group_from = Self::group_index(&keys, *from, &key);
+                            let group_to = Self::group_index(&keys, *to, &key);
+                            keys.insert(*to, key.clone());
+                            if group_from != group_to {
+                                dest.get(&key).unwrap().move_item(pc, group_from, group_to);
+                            }
+                        },
+                    }
+                }
+
+                // Remove empty groups last so a group that's emptied and refilled in the
+                // same event isn't reported as replaced
+                let empty =
+                    dest
+                        .borrow_values()
+                        .iter()
+                        .filter(|(_, group)| group.borrow_values().is_empty())
+                        .map(|(k, _)| k.clone())
+                        .collect::<Vec<_>>();
+                for key in empty {
+                    dest.remove(pc, &key);
+                }
+            }
+
+            fn next_values(&self) -> Vec<Value> {
+                let Some(dest) = self.dest.upgrade() else {
+                    return vec![];
+                };
+                let mut out = vec![dest.into_value()];
+                out.extend(dest.borrow_values().values().map(|group| group.into_value()));
+                return out;
+            }
+        }
+
+        let (dest, keys, skip) = {
+            let self2 = self.0.mut_.borrow();
+            let mut groups = HashMap::<K, std::vec::Vec<T>>::new();
+            let mut keys = vec![];
+            for value in &self2.value {
+                let k = key(value);
+                groups.entry(k.clone()).or_default().push(value.clone());
+                keys.push(k);
+            }
+            (Map::new(groups.into_iter().map(|(k, v)| (k, List::new(v))).collect()), keys, self2.changes.len())
+        };
+        let link = Link::new(pc, GroupLink {
+            source: self.clone(),
+            skip: Cell::new(skip),
+            keys: RefCell::new(keys),
+            dest: dest.weak(),
+            key: Box::new(key),
+        });
+        self.add_next(&link);
+        dest.own_link(link);
+        return dest;
+    }
+
+    /// Create an index of the positions of this list's elements by the key returned
+    /// by `key`, for looking up elements without scanning the list.  Multiple elements
+    /// may have the same key.  The index keeps itself updated as long as it exists.
+    ///
+    /// `key` is called once per new element.
+    pub fn index_by<K: Clone + Eq + Hash + 'static>(
+        &self,
+        pc: &mut ProcessingContext,
+        key: impl Fn(&T) -> K + 'static,
+    ) -> Index<K> {
+        struct IndexLink<K: Clone + Eq + Hash, T: Clone> {
+            source: List<T>,
+            skip: Cell<usize>,
+            dest: WeakIndex<K>,
+            key: Box<dyn Fn(&T) -> K>,
+        }
+
+        impl<K: Clone + Eq + Hash + 'static, T: Clone + 'static> LinkTrait for IndexLink<K, T> {
+            fn call(&self, pc: &mut ProcessingContext) {
+                let Some(dest) = self.dest.upgrade() else {
+                    return;
+                };
+                let changes = self.source.borrow_changes();
+                for change in changes.iter().skip(self.skip.replace(0)) {
+                    match change {
+                        Change::Splice { offset, remove, add, .. } => {
+                            dest.splice_(pc, *offset, *remove, add.iter().map(&self.key).collect());
+                        },
+                        Change::Replace { offset, value, .. } => {
+                            dest.set_(pc, *offset, (self.key)(value));
+                        },
+                        Change::Move { from, to } => {
+                            dest.move_(pc, *from, *to);
+                        },
+                    }
+                }
+            }
+
+            fn next_values(&self) -> Vec<Value> {
+                return self.dest.upgrade().map(|d| d.into_value()).into_iter().collect();
+            }
+        }
+
+        let (dest, skip) = {
+            let self2 = self.0.mut_.borrow();
+            (Index::new_(self2.value.iter().map(&key).collect()), self2.changes.len())
+        };
+        let link = Link::new(pc, IndexLink {
+            source: self.clone(),
+            skip: Cell::new(skip),
+            dest: dest.weak(),
+            key: Box::new(key),
+        });
+        self.add_next(&link);
+        dest.own_link(link);
+        return dest;
+    }
+
+    /// Create a value derived from this list by folding over its elements.  `add`
+    /// updates the value with a new element and `remove` undoes `add` for an element
+    /// that was removed, so the value is updated incrementally without rescanning the
+    /// list.  The value keeps itself updated as long as it exists and shouldn't be
+    /// modified directly.
+    ///
+    /// This keeps a copy of the elements of this list, to pass removed elements to
+    /// `remove`.
+    pub fn fold<U: PartialEq + Clone + 'static>(
+        &self,
+        pc: &mut ProcessingContext,
+        init: U,
+        add: impl Fn(U, &T) -> U + 'static,
+        remove: impl Fn(U, &T) -> U + 'static,
+    ) -> HistPrim<U> {
+        return self.fold_(
+            pc,
+            init,
+            Box::new(move |acc, v| Some(add(acc, v))),
+            Box::new(move |acc, v| Some(remove(acc, v))),
+        );
+    }
+
+    /// Like `fold` but `add` and `remove` return `None` if the value can't be updated
+    /// incrementally, in which case the value is recalculated from all elements.
+    fn fold_<U: PartialEq + Clone + 'static>(
+        &self,
+        pc: &mut ProcessingContext,
+        init: U,
+        add: FoldFn<U, T>,
+        remove: FoldFn<U, T>,
+    ) -> HistPrim<U> {
+        struct FoldLink<T: Clone, U: PartialEq + Clone> {
+            source: List<T>,
+            skip: Cell<usize>,
+            /// The elements of `source` as of the last call
+            shadow: RefCell<std::vec::Vec<T>>,
+            dest: WeakHistPrim<U>,
+            init: U,
+            add: FoldFn<U, T>,
+            remove: FoldFn<U, T>,
+        }
+
+        fn rescan<T, U: Clone>(init: &U, add: &FoldFn<U, T>, values: &[T]) -> U {
+            let mut acc = init.clone();
+            for v in values {
+                acc = add(acc, v).expect("Fold add must succeed when rescanning");
+            }
+            return acc;
+        }
+
+        impl<T: Clone + 'static, U: PartialEq + Clone + 'static> FoldLink<T, U> {
+
+            /// Apply `change` to `acc`, with `shadow` the elements before the change.
+            fn update(&self, acc: U, change: &Change<T>, shadow: &[T]) -> Option<U> {
+                let mut acc = acc;
+                match change {
+                    Change::Splice { offset, remove, add, .. } => {
+                        for v in &shadow[*offset .. *offset + *remove] {
+                            acc = (self.remove)(acc, v)?;
+                        }
+                        for v in add {
+                            acc = (self.add)(acc, v)?;
+                        }
+                    },
+                    Change::Replace { offset, value, .. } => {
+                        acc = (self.remove)(acc, &shadow[*offset])?;
+                        acc = (self.add)(acc, value)?;
+                    },
+                    Change::Move { .. } => { },
+                }
+                return Some(acc);
+            }
+        }
+
+        impl<T: Clone + 'static, U: PartialEq + Clone + 'static> LinkTrait for FoldLink<T, U> {
+            fn call(&self, pc: &mut ProcessingContext) {
+                let Some(dest) = self.dest.upgrade() else {
+                    return;
+                };
+                let mut acc = Some(dest.get());
+                let mut shadow = self.shadow.borrow_mut();
+                let changes = self.source.borrow_changes();
+                for change in changes.iter().skip(self.skip.replace(0)) {
+                    if let Some(acc1) = acc {
+                        acc = self.update(acc1, change, &shadow);
+                    }
+                    change.apply_to(&mut *shadow, |v| v.clone());
+                }
+                drop(changes);
+                let acc = match acc {
+                    Some(acc) => acc,
+                    None => rescan(&self.init, &self.add, &self.source.borrow_values()),
+                };
+                dest.set(pc, acc);
+            }
+
+            fn next_values(&self) -> Vec<Value> {
+                return self.dest.upgrade().map(|d| d.into_value()).into_iter().collect();
+            }
+        }
+
+        let (shadow, skip) = {
+            let self2 = self.0.mut_.borrow();
+            (self2.value.clone(), self2.changes.len())
+        };
+        let dest = HistPrim::new(pc, rescan(&init, &add, &shadow));
+        let link = Link::new(pc, FoldLink {
+            source: self.clone(),
+            skip: Cell::new(skip),
+            shadow: RefCell::new(shadow),
+            dest: dest.weak(),
+            init: init,
+            add: add,
+            remove: remove,
+        });
+        self.add_next(&link);
+        dest.own_link(link);
+        return dest;
+    }
+
+    /// A value with the number of elements in the list, updated incrementally.
+    pub fn len_prim(&self, pc: &mut ProcessingContext) -> HistPrim<usize> {
+        struct LenLink<T: Clone> {
+            source: List<T>,
+            skip: Cell<usize>,
+            dest: WeakHistPrim<usize>,
+        }
+
+        impl<T: Clone + 'static> LinkTrait for LenLink<T> {
+            fn call(&self, pc: &mut ProcessingContext) {
+                let Some(dest) = self.dest.upgrade() else {
+                    return;
+                };
+                let mut len = dest.get();
+                for change in self.source.borrow_changes().iter().skip(self.skip.replace(0)) {
+                    if let Change::Splice { remove, add, .. } = change {
+                        len = len + add.len() - *remove;
+                    }
+                }
+                dest.set(pc, len);
+            }
+
+            fn next_values(&self) -> Vec<Value> {
+                return self.dest.upgrade().map(|d| d.into_value()).into_iter().collect();
+            }
+        }
+
+        let (len, skip) = {
+            let self2 = self.0.mut_.borrow();
+            (self2.value.len(), self2.changes.len())
+        };
+        let dest = HistPrim::new(pc, len);
+        let link = Link::new(pc, LenLink {
+            source: self.clone(),
+            skip: Cell::new(skip),
+            dest: dest.weak(),
+        });
+        self.add_next(&link);
+        dest.own_link(link);
+        return dest;
+    }
+
+    /// A value with the number of elements in the list for which `predicate` returns
+    /// true, updated incrementally.
+    pub fn count_where(
+        &self,
+        pc: &mut ProcessingContext,
+        predicate: impl Fn(&T) -> bool + 'static,
+    ) -> HistPrim<usize> {
+        let predicate = Rc::new(predicate);
+        return self.fold(pc, 0, {
+            let predicate = predicate.clone();
+            move |acc, v| acc + predicate(v) as usize
+        }, move |acc, v| acc - predicate(v) as usize);
+    }
+
     /// The current state of this vec.  A `Deref` wrapper around the internal `Vec`. If
     /// you want to iterate them, you'll need to call `.iter()` explicitly due to deref
     /// limitations.
@@ -186,9 +1395,41 @@ impl<T: Clone + 'static> List<T> {
         return ValuesRef(self.0.mut_.borrow());
     }
 
+    /// Create a cursor that accumulates all changes to this list from now until they're
+    /// retrieved with `ListCursor::drain`, independent of events.  This is for
+    /// consumers outside the graph, like a render loop that polls once per frame.
+    pub fn cursor(&self) -> ListCursor<T> {
+        let out = ListCursor {
+            list: self.weak(),
+            state: Rc::new(RefCell::new(ListCursor_ {
+                changes: vec![],
+                max_pending: None,
+                overflowed: false,
+            })),
+        };
+        self.0.mut_.borrow_mut().cursors.push(Rc::downgrade(&out.state));
+        return out;
+    }
+
+    /// Apply all changes from the current event to `sink` (see `Change::apply_to`).
+    /// If `sink` mirrored this list before the changes, it will mirror it afterwards.
+    ///
+    /// Borrows the list, `sink` and `map` must not modify it.
+    pub fn apply_changes<U, S: ChangeSink<U> + ?Sized>(&self, sink: &mut S, mut map: impl FnMut(&T) -> U) {
+        for change in self.0.mut_.borrow().changes.iter() {
+            change.apply_to(sink, &mut map);
+        }
+    }
+
+    /// Like `borrow_changes` but with overlapping and adjacent changes merged (see
+    /// `compact_changes`).  This copies the changes.
+    pub fn compacted_changes(&self) -> std::vec::Vec<Change<T>> {
+        return compact_changes(&self.0.mut_.borrow().changes);
+    }
+
     /// Any changes during the current event handling that occurred to value to get it
-    /// to its current state.  You can use them as splice inputs for a second list to
-    /// synchronize them.  A `Deref` wrapper around an internal `Vec`.  If you want to
+    /// to its current state.  You can replay them on a second list to synchronize
+    /// them.  A `Deref` wrapper around an internal `Vec`.  If you want to
     /// iterate them, you'll need to call `.iter()` explicitly due to deref limitations.
     ///
     /// Borrows the list, must be released before calling other mutate methods.
@@ -197,6 +1438,515 @@ impl<T: Clone + 'static> List<T> {
     }
 }
 
+impl<T: Clone + PartialEq + Default + Add<Output = T> + Sub<Output = T> + 'static> List<T> {
+    /// A value with the sum of the elements in the list, updated incrementally.
+    pub fn sum(&self, pc: &mut ProcessingContext) -> HistPrim<T> {
+        return self.fold(pc, T::default(), |acc, v| acc + v.clone(), |acc, v| acc - v.clone());
+    }
+}
+
+impl<T: Clone + Ord + 'static> List<T> {
+    /// A value with the smallest element in the list or `None` if the list is empty.
+    /// This is updated incrementally, except when the smallest element is removed.
+    pub fn min(&self, pc: &mut ProcessingContext) -> HistPrim<Option<T>> {
+        return self.extreme_(pc, std::cmp::Ordering::Less);
+    }
+
+    /// A value with the largest element in the list or `None` if the list is empty.
+    /// This is updated incrementally, except when the largest element is removed.
+    pub fn max(&self, pc: &mut ProcessingContext) -> HistPrim<Option<T>> {
+        return self.extreme_(pc, std::cmp::Ordering::Greater);
+    }
+
+    fn extreme_(&self, pc: &mut ProcessingContext, better: std::cmp::Ordering) -> HistPrim<Option<T>> {
+        return self.fold_(pc, None, Box::new(move |acc: Option<T>, v: &T| {
+            match acc {
+                Some(acc) if v.cmp(&acc) != better => return Some(Some(acc)),
+                _ => return Some(Some(v.clone())),
+            }
+        }), Box::new(|acc: Option<T>, v: &T| {
+            if acc.as_ref() == Some(v) {
+                // Removed the current extreme, need to rescan
+                return None;
+            }
+            return Some(acc);
+        }));
+    }
+}
+
+impl<T: Clone + PartialEq + 'static> List<T> {
+    /// Replace the contents of the list with `values`, recording a minimal sequence of
+    /// splices (per Myers' diff) rather than clearing it, so downstream consumers only
+    /// need to update the elements that actually changed.  Triggers processing.
+    pub fn replace_all(&self, pc: &mut ProcessingContext, values: std::vec::Vec<T>) {
+        let mut self2 = self.0.mut_.borrow_mut();
+        let ops = diff(&self2.value, &values, |a, b| a == b);
+        self.apply_diff_(&mut self2, pc, ops, values, |_, _| true);
+        self.trim_(&mut self2, pc);
+    }
+
+    /// Modify a copy of the elements with direct `Vec` access (ex: `retain`, `sort`,
+    /// `dedup`), then record the difference from the original elements like
+    /// `replace_all`.  Triggers processing if anything changed.
+    pub fn mutate<R>(&self, pc: &mut ProcessingContext, f: impl FnOnce(&mut std::vec::Vec<T>) -> R) -> R {
+        let mut self2 = self.0.mut_.borrow_mut();
+        let mut values = self2.value.clone();
+        let out = f(&mut values);
+        let ops = diff(&self2.value, &values, |a, b| a == b);
+        self.apply_diff_(&mut self2, pc, ops, values, |_, _| true);
+        self.trim_(&mut self2, pc);
+        return out;
+    }
+}
+
+enum Merge<T: Clone> {
+    No,
+    Cancelled,
+    Merged(Change<T>),
+}
+
+struct SpliceRef<'a, T> {
+    offset: usize,
+    remove: usize,
+    add: &'a [T],
+    removed: Option<&'a [T]>,
+}
+
+/// Merge `b` into a splice `a` where `b` immediately follows `a`, if the modified
+/// ranges touch.
+fn merge_splices<T: Clone>(a: SpliceRef<T>, b: SpliceRef<T>) -> Merge<T> {
+    let SpliceRef { offset: a_offset, remove: a_remove, add: a_add, removed: a_removed } = a;
+    let SpliceRef { offset: b_offset, remove: b_remove, add: b_add, removed: b_removed } = b;
+    let a_end = a_offset + a_add.len();
+    let b_end = b_offset + b_remove;
+    if b_offset > a_end || b_end < a_offset {
+        return Merge::No;
+    }
+
+    // Elements `b` removes before and after the elements `a` added were in the list
+    // before `a`
+    let before = a_offset.saturating_sub(b_offset);
+    let after = b_end.saturating_sub(a_end);
+    let mut add = vec![];
+    add.extend(a_add[.. b_offset.saturating_sub(a_offset)].iter().cloned());
+    add.extend(b_add.iter().cloned());
+    add.extend(a_add[b_end.min(a_end) - a_offset ..].iter().cloned());
+    let removed = match (a_removed, b_removed) {
+        (Some(a_removed), Some(b_removed)) => {
+            let mut removed = vec![];
+            removed.extend(b_removed[.. before].iter().cloned());
+            removed.extend(a_removed.iter().cloned());
+            removed.extend(b_removed[b_remove - after ..].iter().cloned());
+            Some(removed)
+        },
+        _ => None,
+    };
+    let remove = a_remove + before + after;
+    if remove == 0 && add.is_empty() {
+        return Merge::Cancelled;
+    }
+    return Merge::Merged(Change::Splice {
+        offset: a_offset.min(b_offset),
+        remove: remove,
+        add: add,
+        removed: removed,
+    });
+}
+
+/// Merge `b` into `a` where `b` immediately follows `a`, if possible.
+fn merge_changes<T: Clone>(a: &Change<T>, b: &Change<T>) -> Merge<T> {
+    match (a, b) {
+        (
+            Change::Splice { offset: a_offset, remove: a_remove, add: a_add, removed: a_removed },
+            Change::Splice { offset: b_offset, remove: b_remove, add: b_add, removed: b_removed },
+        ) => {
+            return merge_splices(SpliceRef {
+                offset: *a_offset,
+                remove: *a_remove,
+                add: a_add,
+                removed: a_removed.as_deref(),
+            }, SpliceRef {
+                offset: *b_offset,
+                remove: *b_remove,
+                add: b_add,
+                removed: b_removed.as_deref(),
+            });
+        },
+        (
+            Change::Splice { offset: a_offset, remove: a_remove, add: a_add, removed: a_removed },
+            Change::Replace { offset: b_offset, value: b_value, .. },
+        ) => {
+            if *b_offset < *a_offset || *b_offset >= *a_offset + a_add.len() {
+                return Merge::No;
+            }
+            let mut add = a_add.clone();
+            add[*b_offset - *a_offset] = b_value.clone();
+            return Merge::Merged(Change::Splice {
+                offset: *a_offset,
+                remove: *a_remove,
+                add: add,
+                removed: a_removed.clone(),
+            });
+        },
+        (
+            Change::Replace { offset: a_offset, old: a_old, .. },
+            Change::Replace { offset: b_offset, value: b_value, .. },
+        ) => {
+            if a_offset != b_offset {
+                return Merge::No;
+            }
+            return Merge::Merged(Change::Replace {
+                offset: *a_offset,
+                value: b_value.clone(),
+                old: a_old.clone(),
+            });
+        },
+        (
+            Change::Replace { offset: a_offset, value: a_value, old: a_old },
+            Change::Splice { offset: b_offset, remove: b_remove, add: b_add, removed: b_removed },
+        ) => {
+            // Only merge if the replaced element is removed, otherwise the replacement
+            // would need to become a splice which is worse for consumers
+            if *a_offset < *b_offset || *a_offset >= *b_offset + *b_remove {
+                return Merge::No;
+            }
+            return merge_splices(SpliceRef {
+                offset: *a_offset,
+                remove: 1,
+                add: std::slice::from_ref(a_value),
+                removed: a_old.as_ref().map(std::slice::from_ref),
+            }, SpliceRef {
+                offset: *b_offset,
+                remove: *b_remove,
+                add: b_add,
+                removed: b_removed.as_deref(),
+            });
+        },
+        (Change::Move { from: a_from, to: a_to }, Change::Move { from: b_from, to: b_to }) => {
+            // Moving the same element twice
+            if a_to == b_from {
+                if a_from == b_to {
+                    return Merge::Cancelled;
+                }
+                return Merge::Merged(Change::Move {
+                    from: *a_from,
+                    to: *b_to,
+                });
+            }
+            return Merge::No;
+        },
+        _ => {
+            return Merge::No;
+        },
+    }
+}
+
+/// Produce an equivalent, possibly shorter sequence of changes by merging
+/// consecutive changes that touch the same elements - for example a `push`
+/// followed by a `pop` cancel out, and consecutive `push`es become a single splice.
+pub fn compact_changes<T: Clone>(changes: &[Change<T>]) -> std::vec::Vec<Change<T>> {
+    let mut out: std::vec::Vec<Change<T>> = vec![];
+    for change in changes {
+        let mut change = change.clone();
+        loop {
+            let Some(last) = out.last() else {
+                out.push(change);
+                break;
+            };
+            match merge_changes(last, &change) {
+                Merge::No => {
+                    out.push(change);
+                    break;
+                },
+                Merge::Cancelled => {
+                    out.pop();
+                    break;
+                },
+                Merge::Merged(merged) => {
+                    out.pop();
+                    change = merged;
+                },
+            }
+        }
+    }
+    return out;
+}
+
+type FilterFn<P, T> = Box<dyn Fn(&P, &T) -> bool>;
+type FoldFn<U, T> = Box<dyn Fn(U, &T) -> Option<U>>;
+type CmpFn<T> = Box<dyn Fn(&T, &T) -> std::cmp::Ordering>;
+
+impl<T: Clone + 'static> List<List<T>> {
+    /// Create a new list derived from this list of lists, with the elements of all the
+    /// inner lists in order.  Changes to this list or to any inner list are translated
+    /// to changes of the corresponding range of the derived list.  The derived list
+    /// keeps itself updated as long as it exists and shouldn't be modified directly.
+    pub fn flatten(&self, pc: &mut ProcessingContext) -> List<T> {
+        struct Inner<T: Clone> {
+            list: List<T>,
+            len: usize,
+            /// Changes already reflected in the output
+            skip: usize,
+        }
+
+        struct FlattenLink<T: Clone> {
+            source: List<List<T>>,
+            skip: Cell<usize>,
+            inners: RefCell<std::vec::Vec<Inner<T>>>,
+            dest: WeakList<T>,
+            /// This link, to subscribe to new inner lists
+            self_link: Rc<RefCell<Weak<Link_>>>,
+            /// The number of occurrences of each inner list, so each is subscribed to
+            /// once and unsubscribed from when the last occurrence is removed
+            subscribed: Rc<RefCell<Subscribed<T>>>,
+        }
+
+        type Subscribed<T> = HashMap<*const List_<T>, usize>;
+
+        fn subscribe<T: Clone + 'static>(subscribed: &RefCell<Subscribed<T>>, self_link: &Link, list: &List<T>) {
+            let mut subscribed = subscribed.borrow_mut();
+            let count = subscribed.entry(Rc::as_ptr(&list.0)).or_insert(0);
+            if *count == 0 {
+                list.add_next(self_link);
+            }
+            *count += 1;
+        }
+
+        fn unsubscribe<T: Clone + 'static>(subscribed: &RefCell<Subscribed<T>>, self_link: &Link, list: &List<T>) {
+            let mut subscribed = subscribed.borrow_mut();
+            let count = subscribed.get_mut(&Rc::as_ptr(&list.0)).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                subscribed.remove(&Rc::as_ptr(&list.0));
+                list.remove_next(self_link);
+            }
+        }
+
+        impl<T: Clone + 'static> LinkTrait for FlattenLink<T> {
+            fn call(&self, pc: &mut ProcessingContext) {
+                let Some(dest) = self.dest.upgrade() else {
+                    return;
+                };
+                let mut inners = self.inners.borrow_mut();
+                let Some(self_link) = self.self_link.borrow().upgrade().map(Link) else {
+                    return;
+                };
+
+                // Inner list changes are independent of the outer list structure, so
+                // process them first with the structure from before this event
+                let mut base = 0;
+                for inner in inners.iter_mut() {
+                    let changes = inner.list.borrow_changes();
+                    for change in changes.iter().skip(std::mem::take(&mut inner.skip)) {
+                        match change {
+                            Change::Splice { offset, remove, add, .. } => {
+                                dest.splice(pc, base + *offset, *remove, add.clone());
+                                inner.len = inner.len + add.len() - *remove;
+                            },
+                            Change::Replace { offset, value, .. } => {
+                                dest.set(pc, base + *offset, value.clone());
+                            },
+                            Change::Move { from, to } => {
+                                dest.move_item(pc, base + *from, base + *to);
+                            },
+                        }
+                    }
+                    base += inner.len;
+                }
+
+                // Then process changes to the outer list.  Newly added inner lists are
+                // added with their current state.
+                let base_of = |inners: &[Inner<T>], index: usize| {
+                    return inners[.. index].iter().map(|i| i.len).sum::<usize>();
+                };
+                let changes = self.source.borrow_changes();
+                for change in changes.iter().skip(self.skip.replace(0)) {
+                    match change {
+                        Change::Splice { offset, remove, add, .. } => {
+                            let base = base_of(&inners, *offset);
+                            let removed = inners.splice(*offset .. *offset + *remove, add.iter().map(|list| {
+                                subscribe(&self.subscribed, &self_link, list);
+                                return Inner {
+                                    len: list.borrow_values().len(),
+                                    list: list.clone(),
+                                    skip: 0,
+                                };
+                            }).collect::<Vec<_>>()).map(|i| {
+                                unsubscribe(&self.subscribed, &self_link, &i.list);
+                                return i.len;
+                            }).sum();
+                            let mut add_values = vec![];
+                            for list in add {
+                                add_values.extend(list.borrow_values().iter().cloned());
+                            }
+                            dest.splice(pc, base, removed, add_values);
+                        },
+                        Change::Replace { offset, value, .. } => {
+                            let base = base_of(&inners, *offset);
+                            let old_len = inners[*offset].len;
+                            let values = value.borrow_values().clone();
+                            subscribe(&self.subscribed, &self_link, value);
+                            let old = std::mem::replace(&mut inners[*offset], Inner {
+                                len: values.len(),
+                                list: value.clone(),
+                                skip: 0,
+                            });
+                            unsubscribe(&self.subscribed, &self_link, &old.list);
+                            dest.splice(pc, base, old_len, values);
+                        },
+                        Change::Move { from, to } => {
+                            let from_base = base_of(&inners, *from);
+                            let inner = inners.remove(*from);
+                            let to_base = base_of(&inners, *to);
+                            for i in 0 .. inner.len {
+                                if from_base < to_base {
+                                    dest.move_item(pc, from_base, to_base + inner.len - 1);
+                                } else {
+                                    dest.move_item(pc, from_base + i, to_base + i);
+                                }
+                            }
+                            inners.insert(*to, inner);
+                        },
+                    }
+                }
+            }
+
+            fn next_values(&self) -> Vec<Value> {
+                return self.dest.upgrade().map(|d| d.into_value()).into_iter().collect();
+            }
+        }
+
+        let (dest, inners, skip) = {
+            let self2 = self.0.mut_.borrow();
+            let mut values = vec![];
+            let mut inners = vec![];
+            for list in &self2.value {
+                let list2 = list.0.mut_.borrow();
+                values.extend(list2.value.iter().cloned());
+                inners.push(Inner {
+                    list: list.clone(),
+                    len: list2.value.len(),
+                    skip: list2.changes.len(),
+                });
+            }
+            (List::new(values), inners, self2.changes.len())
+        };
+        let self_link = Rc::new(RefCell::new(Weak::new()));
+        let subscribed = Rc::new(RefCell::new(HashMap::new()));
+        let link = Link::new(pc, FlattenLink {
+            source: self.clone(),
+            skip: Cell::new(skip),
+            inners: RefCell::new(inners),
+            dest: dest.weak(),
+            self_link: self_link.clone(),
+            subscribed: subscribed.clone(),
+        });
+        *self_link.borrow_mut() = Rc::downgrade(&link.0);
+        self.add_next(&link);
+        for list in self.borrow_values().iter() {
+            subscribe(&subscribed, &link, list);
+        }
+        dest.0.mut_.borrow_mut().links.push(link);
+        return dest;
+    }
+
+    /// Create a new list derived from `sources`, with the elements of each in order.
+    /// See `flatten`.
+    pub fn concat(pc: &mut ProcessingContext, sources: &[List<T>]) -> List<T> {
+        return List::new(sources.to_vec()).flatten(pc);
+    }
+}
+
+/// Steps to turn one sequence into another, in order.  `Equal` and `Insert` have the
+/// index of the element in the new sequence.
+enum DiffOp {
+    Equal(usize),
+    Delete,
+    Insert(usize),
+}
+
+/// Produce a minimal edit script from `old` to `new` using Myers' algorithm.
+fn diff<T>(old: &[T], new: &[T], eq: impl Fn(&T, &T) -> bool) -> std::vec::Vec<DiffOp> {
+    let mut prefix = 0;
+    while prefix < old.len() && prefix < new.len() && eq(&old[prefix], &new[prefix]) {
+        prefix += 1;
+    }
+    let mut suffix = 0;
+    while suffix < old.len() - prefix && suffix < new.len() - prefix &&
+        eq(&old[old.len() - 1 - suffix], &new[new.len() - 1 - suffix]) {
+        suffix += 1;
+    }
+    let a = &old[prefix .. old.len() - suffix];
+    let b = &new[prefix .. new.len() - suffix];
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let max = n + m;
+
+    // Forward pass, keeping the furthest reaching x for each diagonal k before each
+    // step d (only diagonals -d..=d are relevant for step d)
+    let vi = |k: isize| (k + max + 1) as usize;
+    let mut v = vec![0isize; 2 * max as usize + 3];
+    let mut trace = vec![];
+    'search: for d in 0 ..= max {
+        trace.push(v[vi(-d) ..= vi(d)].to_vec());
+        let mut k = -d;
+        while k <= d {
+            let mut x = if k == -d || (k != d && v[vi(k - 1)] < v[vi(k + 1)]) {
+                v[vi(k + 1)]
+            } else {
+                v[vi(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && eq(&a[x as usize], &b[y as usize]) {
+                x += 1;
+                y += 1;
+            }
+            v[vi(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+            k += 2;
+        }
+    }
+
+    // Walk back from the end to build the script
+    let mut rev_ops = vec![];
+    let mut x = n;
+    let mut y = m;
+    for d in (1 .. trace.len() as isize).rev() {
+        let v = &trace[d as usize];
+        let vd = |k: isize| v[(k + d) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && vd(k - 1) < vd(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = vd(prev_k);
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            rev_ops.push(DiffOp::Equal(prefix + y as usize));
+        }
+        if prev_k == k + 1 {
+            rev_ops.push(DiffOp::Insert(prefix + prev_y as usize));
+        } else {
+            rev_ops.push(DiffOp::Delete);
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    while y > 0 {
+        y -= 1;
+        rev_ops.push(DiffOp::Equal(prefix + y as usize));
+    }
+    let mut out = (0 .. prefix).map(DiffOp::Equal).collect::<std::vec::Vec<_>>();
+    out.extend(rev_ops.into_iter().rev());
+    out.extend((new.len() - suffix .. new.len()).map(DiffOp::Equal));
+    return out;
+}
+
 impl<T: Clone + 'static> IntoValue for List<T> {
     fn into_value(&self) -> Value {
         return Value(self.0.clone());
@@ -209,6 +1959,75 @@ impl<T: Clone + 'static> WeakList<T> {
     }
 }
 
+struct ListCursor_<T: Clone> {
+    changes: std::vec::Vec<Change<T>>,
+    max_pending: Option<usize>,
+    overflowed: bool,
+}
+
+impl<T: Clone> ListCursor_<T> {
+    fn push(&mut self, change: &Change<T>) {
+        if self.overflowed {
+            return;
+        }
+        self.changes.push(change.clone());
+        let Some(max_pending) = self.max_pending else {
+            return;
+        };
+        if self.changes.len() <= max_pending {
+            return;
+        }
+        self.changes = compact_changes(&self.changes);
+        if self.changes.len() > max_pending {
+            self.changes.clear();
+            self.overflowed = true;
+        }
+    }
+}
+
+/// The result of draining a `ListCursor`.
+pub enum CursorDrain<T: Clone> {
+    /// Changes since the last drain, in order (see `List::borrow_changes`).
+    Changes(std::vec::Vec<Change<T>>),
+    /// The cursor fell too far behind, so the changes were discarded.  This has the
+    /// current values of the list - consumers should rebuild their state from
+    /// scratch.
+    Reset(std::vec::Vec<T>),
+}
+
+/// A handle accumulating changes to a list across events, see `List::cursor`.
+pub struct ListCursor<T: Clone> {
+    list: WeakList<T>,
+    state: Rc<RefCell<ListCursor_<T>>>,
+}
+
+impl<T: Clone + 'static> ListCursor<T> {
+    /// Limit the number of accumulated changes.  When there are more, the changes are
+    /// compacted (see `compact_changes`), and if there are still too many they're
+    /// discarded and the next `drain` will return `CursorDrain::Reset`.
+    pub fn set_max_pending(&self, max_pending: Option<usize>) {
+        self.state.borrow_mut().max_pending = max_pending;
+    }
+
+    /// Whether any changes have happened since the last drain.
+    pub fn has_changes(&self) -> bool {
+        let state = self.state.borrow();
+        return state.overflowed || !state.changes.is_empty();
+    }
+
+    /// Take all changes since the last drain (or since the cursor was created).
+    pub fn drain(&self) -> CursorDrain<T> {
+        let mut state = self.state.borrow_mut();
+        if state.overflowed {
+            state.overflowed = false;
+            return CursorDrain::Reset(
+                self.list.upgrade().map(|l| l.borrow_values().clone()).unwrap_or_default(),
+            );
+        }
+        return CursorDrain::Changes(std::mem::take(&mut state.changes));
+    }
+}
+
 pub struct ValuesRef<'a, T: Clone + 'static>(Ref<'a, ListMut_<T>>);
 
 impl<'a, T: Clone + 'static> Deref for ValuesRef<'a, T> {
@@ -228,3 +2047,36 @@ impl<'a, T: Clone + 'static> Deref for ChangesRef<'a, T> {
         return &self.0.changes;
     }
 }
+
+#[test]
+fn flatten_subscribes_once() {
+    let eg = crate::EventGraph::new();
+    let inner = List::new(vec![1]);
+    let (outer, flat) = eg.event(|pc| {
+        let outer = List::new(vec![inner.clone(), inner.clone()]);
+        let flat = outer.flatten(pc);
+        return (outer, flat);
+    }).unwrap();
+    let subscriptions = || inner.0.mut_.borrow_mut().next().len();
+    assert_eq!(subscriptions(), 1);
+    for _ in 0 .. 3 {
+        eg.event(|pc| {
+            outer.remove(pc, 0);
+            outer.push(pc, inner.clone());
+        });
+        eg.event(|pc| {
+            outer.set(pc, 1, inner.clone());
+        });
+    }
+    assert_eq!(subscriptions(), 1);
+    eg.event(|pc| {
+        outer.clear(pc);
+    });
+    assert_eq!(subscriptions(), 0);
+    eg.event(|pc| {
+        inner.push(pc, 2);
+        outer.push(pc, inner.clone());
+    });
+    assert_eq!(subscriptions(), 1);
+    assert_eq!(flat.borrow_values().clone(), vec![1, 2]);
+}
@@ -0,0 +1,239 @@
+use std::{
+    rc::{
+        Rc,
+        Weak,
+    },
+    cell::{
+        RefCell,
+        Ref,
+    },
+    collections::{
+        HashMap,
+        hash_map::Entry,
+    },
+    hash::Hash,
+    ops::Deref,
+};
+use crate::{
+    core::{
+        ValueTrait,
+        ProcessingContext,
+        Cleanup,
+        Link_,
+        IntoValue,
+        Value,
+    },
+    Link,
+};
+
+pub struct MapMut_<K: Clone + Eq + Hash, V: Clone> {
+    value: HashMap<K, V>,
+    /// The value before the current event of each key modified during the event,
+    /// `None` if the key wasn't present.
+    old: HashMap<K, Option<V>>,
+    next: std::vec::Vec<Weak<Link_>>,
+    /// Links that keep this map updated, for derived maps
+    links: std::vec::Vec<Link>,
+}
+
+impl<K: Clone + Eq + Hash, V: Clone> MapMut_<K, V> {
+    fn next(&mut self) -> Vec<crate::Link> {
+        let mut out = vec![];
+        out.reserve(self.next.len());
+        self.next.retain_mut(|e| {
+            match e.upgrade() {
+                Some(e) => {
+                    out.push(Link(e.clone()));
+                    return true;
+                },
+                None => {
+                    return false;
+                },
+            }
+        });
+        return out;
+    }
+}
+
+struct Map_<K: Clone + Eq + Hash, V: Clone> {
+    mut_: RefCell<MapMut_<K, V>>,
+}
+
+impl<K: Clone + Eq + Hash, V: Clone> ValueTrait for Map_<K, V> {
+    fn next_links(&self) -> Vec<crate::Link> {
+        return self.mut_.borrow_mut().next();
+    }
+}
+
+impl<K: Clone + Eq + Hash, V: Clone> Cleanup for Map_<K, V> {
+    fn clean(&self) {
+        self.mut_.borrow_mut().old.clear();
+    }
+}
+
+/// A value that manages an unordered mapping of keys to values.
+#[derive(Clone)]
+pub struct Map<K: Clone + Eq + Hash, V: Clone>(Rc<Map_<K, V>>);
+
+#[derive(Clone)]
+pub struct WeakMap<K: Clone + Eq + Hash, V: Clone>(Weak<Map_<K, V>>);
+
+impl<K: Clone + Eq + Hash + 'static, V: Clone + 'static> Map<K, V> {
+    pub fn new(initial: HashMap<K, V>) -> Self {
+        return Map(Rc::new(Map_ { mut_: RefCell::new(MapMut_ {
+            value: initial,
+            old: HashMap::new(),
+            next: vec![],
+            links: vec![],
+        }) }));
+    }
+
+    /// Used internally by the `link!` macro to establish graph edges between an input
+    /// value and the link.
+    pub fn add_next(&self, link: &Link) {
+        self.0.mut_.borrow_mut().next.push(Rc::downgrade(&link.0));
+    }
+
+    /// Get a weak reference to the map.
+    pub fn weak(&self) -> WeakMap<K, V> {
+        return WeakMap(Rc::downgrade(&self.0));
+    }
+
+    pub(crate) fn own_link(&self, link: Link) {
+        self.0.mut_.borrow_mut().links.push(link);
+    }
+
+    /// Record the pre-event value of `key` if this is the first modification to it
+    /// during the event.
+    fn touch_(&self, self2: &mut MapMut_<K, V>, pc: &mut ProcessingContext, key: &K) {
+        let first_change = self2.old.is_empty();
+        if let Entry::Vacant(e) = self2.old.entry(key.clone()) {
+            e.insert(self2.value.get(key).cloned());
+        }
+        if first_change {
+            pc.1.cleanup.push(self.0.clone());
+            pc.1.mark_changed(self2.next());
+        }
+    }
+
+    /// Add or replace an element, returning the replaced value; triggers processing.
+    pub fn insert(&self, pc: &mut ProcessingContext, key: K, value: V) -> Option<V> {
+        let mut self2 = self.0.mut_.borrow_mut();
+        self.touch_(&mut self2, pc, &key);
+        return self2.value.insert(key, value);
+    }
+
+    /// Remove an element, returning the value or None if there was no such element;
+    /// triggers processing if the element existed.
+    pub fn remove(&self, pc: &mut ProcessingContext, key: &K) -> Option<V> {
+        let mut self2 = self.0.mut_.borrow_mut();
+        if !self2.value.contains_key(key) {
+            return None;
+        }
+        self.touch_(&mut self2, pc, key);
+        return self2.value.remove(key);
+    }
+
+    /// Modify an element in place.  Returns false and does nothing if there was no such
+    /// element; triggers processing otherwise.
+    pub fn modify(&self, pc: &mut ProcessingContext, key: &K, f: impl FnOnce(&mut V)) -> bool {
+        let mut self2 = self.0.mut_.borrow_mut();
+        if !self2.value.contains_key(key) {
+            return false;
+        }
+        self.touch_(&mut self2, pc, key);
+        f(self2.value.get_mut(key).unwrap());
+        return true;
+    }
+
+    /// Clears the collection; triggers processing.
+    pub fn clear(&self, pc: &mut ProcessingContext) {
+        let mut self2 = self.0.mut_.borrow_mut();
+        let keys = self2.value.keys().cloned().collect::<Vec<_>>();
+        for key in &keys {
+            self.touch_(&mut self2, pc, key);
+        }
+        self2.value.clear();
+    }
+
+    /// Get a copy of the value for `key`.
+    pub fn get(&self, key: &K) -> Option<V> {
+        return self.0.mut_.borrow().value.get(key).cloned();
+    }
+
+    /// The current state of the map.  A `Deref` wrapper around the internal
+    /// `HashMap`.
+    ///
+    /// Borrows the map, must be released before calling other mutate methods.
+    pub fn borrow_values<'a>(&'a self) -> MapValuesRef<'a, K, V> {
+        return MapValuesRef(self.0.mut_.borrow());
+    }
+
+    /// The net changes during the current event handling.  A `Deref` wrapper around a
+    /// map of each modified key to its value before the event (`None` if it didn't
+    /// exist), with methods to get inserted, removed, and updated elements.
+    ///
+    /// Borrows the map, must be released before calling other mutate methods.
+    pub fn borrow_changes<'a>(&'a self) -> MapChangesRef<'a, K, V> {
+        return MapChangesRef(self.0.mut_.borrow());
+    }
+}
+
+impl<K: Clone + Eq + Hash + 'static, V: Clone + 'static> IntoValue for Map<K, V> {
+    fn into_value(&self) -> Value {
+        return Value(self.0.clone());
+    }
+}
+
+impl<K: Clone + Eq + Hash + 'static, V: Clone + 'static> WeakMap<K, V> {
+    pub fn upgrade(&self) -> Option<Map<K, V>> {
+        return Some(Map(self.0.upgrade()?));
+    }
+}
+
+pub struct MapValuesRef<'a, K: Clone + Eq + Hash + 'static, V: Clone + 'static>(Ref<'a, MapMut_<K, V>>);
+
+impl<'a, K: Clone + Eq + Hash + 'static, V: Clone + 'static> Deref for MapValuesRef<'a, K, V> {
+    type Target = HashMap<K, V>;
+
+    fn deref(&self) -> &Self::Target {
+        return &self.0.value;
+    }
+}
+
+pub struct MapChangesRef<'a, K: Clone + Eq + Hash + 'static, V: Clone + 'static>(Ref<'a, MapMut_<K, V>>);
+
+impl<'a, K: Clone + Eq + Hash + 'static, V: Clone + 'static> MapChangesRef<'a, K, V> {
+    /// Keys that didn't exist before the event, with their new values.
+    pub fn inserted(&self) -> impl Iterator<Item = (&K, &V)> {
+        return self.0.old.iter().filter_map(|(k, old)| match (old, self.0.value.get(k)) {
+            (None, Some(v)) => Some((k, v)),
+            _ => None,
+        });
+    }
+
+    /// Keys that existed before the event but no longer exist, with their old values.
+    pub fn removed(&self) -> impl Iterator<Item = (&K, &V)> {
+        return self.0.old.iter().filter_map(|(k, old)| match (old, self.0.value.get(k)) {
+            (Some(old), None) => Some((k, old)),
+            _ => None,
+        });
+    }
+
+    /// Keys that existed before and after the event and were modified, with their old
+    /// and new values.
+    pub fn updated(&self) -> impl Iterator<Item = (&K, &V, &V)> {
+        return self.0.old.iter().filter_map(|(k, old)| match (old, self.0.value.get(k)) {
+            (Some(old), Some(v)) => Some((k, old, v)),
+            _ => None,
+        });
+    }
+}
+
+impl<'a, K: Clone + Eq + Hash + 'static, V: Clone + 'static> Deref for MapChangesRef<'a, K, V> {
+    type Target = HashMap<K, Option<V>>;
+
+    fn deref(&self) -> &Self::Target {
+        return &self.0.old;
+    }
+}
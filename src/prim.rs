@@ -13,6 +13,7 @@ use std::{
 use crate::{
     core::{
         Id,
+        new_id,
         ValueTrait,
         ProcessingContext,
         Cleanup,
@@ -36,6 +37,7 @@ impl<T: 'static> PrimMut_<T> {
 }
 
 pub(crate) struct Prim_<T> {
+    pub(crate) id: Id,
     mut_: RefCell<PrimMut_<T>>,
 }
 
@@ -74,11 +76,14 @@ pub struct WeakPrim<T>(Weak<Prim_<T>>);
 
 impl<T: 'static> Prim<T> {
     pub fn new(initial: T) -> Self {
-        return Prim(Rc::new(Prim_ { mut_: RefCell::new(PrimMut_ {
-            value: initial,
-            first_change: true,
-            next: vec![],
-        }) }));
+        return Prim(Rc::new(Prim_ {
+            id: new_id(),
+            mut_: RefCell::new(PrimMut_ {
+                value: initial,
+                first_change: true,
+                next: vec![],
+            }),
+        }));
     }
 
     /// Used internally by the `link!` macro to establish graph edges between an input
@@ -103,11 +108,7 @@ impl<T: 'static> Prim<T> {
         }
         if first_change {
             pc.1.cleanup.push(self.0.clone());
-            if !pc.1.processing {
-                for l in self.0.next_links() {
-                    pc.1.step1_stacked_links.push((true, l));
-                }
-            }
+            pc.1.mark_changed(self.0.next_links());
         }
     }
 
@@ -115,6 +116,11 @@ impl<T: 'static> Prim<T> {
     pub fn borrow<'a>(&'a self) -> ValueRef<'a, T> {
         return ValueRef(self.0.mut_.borrow());
     }
+
+    /// Whether the value was set during the current event.
+    pub(crate) fn changed(&self) -> bool {
+        return !self.0.mut_.borrow().first_change;
+    }
 }
 
 impl<T: 'static> IntoValue for Prim<T> {
@@ -143,6 +149,9 @@ pub struct HistPrimMut_<T: PartialEq + Clone> {
     value: T,
     previous_value: Option<T>,
     next: Vec<Weak<Link_>>,
+    /// For derived values, the links that maintain this value.  They're owned by the
+    /// value so they stay alive as long as the value does.
+    links: Vec<Link>,
 }
 
 impl<T: PartialEq + Clone + 'static> HistPrimMut_<T> {
@@ -201,6 +210,7 @@ impl<T: PartialEq + Clone + 'static> HistPrim<T> {
                 value: initial,
                 previous_value: None,
                 next: vec![],
+                links: vec![],
             }),
         }));
     }
@@ -216,6 +226,10 @@ impl<T: PartialEq + Clone + 'static> HistPrim<T> {
         return WeakHistPrim(Rc::downgrade(&self.0));
     }
 
+    pub(crate) fn own_link(&self, link: Link) {
+        self.0.mut_.borrow_mut().links.push(link);
+    }
+
     /// Modify the value and mark downstream links as needing to be rerun.
     pub fn set(&self, pc: &mut ProcessingContext, mut value: T) {
         let first_change;
@@ -230,11 +244,7 @@ impl<T: PartialEq + Clone + 'static> HistPrim<T> {
         }
         if first_change {
             pc.1.cleanup.push(self.0.clone());
-            if !pc.1.processing {
-                for l in self.0.next_links() {
-                    pc.1.step1_stacked_links.push((true, l));
-                }
-            }
+            pc.1.mark_changed(self.0.next_links());
         }
     }
 
@@ -0,0 +1,209 @@
+use std::{
+    rc::{
+        Rc,
+        Weak,
+    },
+    cell::{
+        RefCell,
+        Ref,
+    },
+    collections::{
+        HashMap,
+        HashSet,
+    },
+    hash::Hash,
+    ops::Deref,
+};
+use crate::{
+    core::{
+        ValueTrait,
+        ProcessingContext,
+        Cleanup,
+        Link_,
+        IntoValue,
+        Value,
+    },
+    Link,
+};
+
+pub struct SetMut_<T: Clone + Eq + Hash> {
+    value: HashSet<T>,
+    /// Whether each element modified during the current event was present before the
+    /// event.
+    old: HashMap<T, bool>,
+    next: std::vec::Vec<Weak<Link_>>,
+}
+
+impl<T: Clone + Eq + Hash> SetMut_<T> {
+    fn next(&mut self) -> Vec<crate::Link> {
+        let mut out = vec![];
+        out.reserve(self.next.len());
+        self.next.retain_mut(|e| {
+            match e.upgrade() {
+                Some(e) => {
+                    out.push(Link(e.clone()));
+                    return true;
+                },
+                None => {
+                    return false;
+                },
+            }
+        });
+        return out;
+    }
+}
+
+struct Set_<T: Clone + Eq + Hash> {
+    mut_: RefCell<SetMut_<T>>,
+}
+
+impl<T: Clone + Eq + Hash> ValueTrait for Set_<T> {
+    fn next_links(&self) -> Vec<crate::Link> {
+        return self.mut_.borrow_mut().next();
+    }
+}
+
+impl<T: Clone + Eq + Hash> Cleanup for Set_<T> {
+    fn clean(&self) {
+        self.mut_.borrow_mut().old.clear();
+    }
+}
+
+/// A value that manages an unordered set of unique values.
+#[derive(Clone)]
+pub struct Set<T: Clone + Eq + Hash>(Rc<Set_<T>>);
+
+#[derive(Clone)]
+pub struct WeakSet<T: Clone + Eq + Hash>(Weak<Set_<T>>);
+
+impl<T: Clone + Eq + Hash + 'static> Set<T> {
+    pub fn new(initial: HashSet<T>) -> Self {
+        return Set(Rc::new(Set_ { mut_: RefCell::new(SetMut_ {
+            value: initial,
+            old: HashMap::new(),
+            next: vec![],
+        }) }));
+    }
+
+    /// Used internally by the `link!` macro to establish graph edges between an input
+    /// value and the link.
+    pub fn add_next(&self, link: &Link) {
+        self.0.mut_.borrow_mut().next.push(Rc::downgrade(&link.0));
+    }
+
+    /// Get a weak reference to the set.
+    pub fn weak(&self) -> WeakSet<T> {
+        return WeakSet(Rc::downgrade(&self.0));
+    }
+
+    /// Record the pre-event membership of `value` if this is the first modification to
+    /// it during the event.
+    fn touch_(&self, self2: &mut SetMut_<T>, pc: &mut ProcessingContext, value: &T) {
+        let first_change = self2.old.is_empty();
+        let was = self2.value.contains(value);
+        self2.old.entry(value.clone()).or_insert(was);
+        if first_change {
+            pc.1.cleanup.push(self.0.clone());
+            pc.1.mark_changed(self2.next());
+        }
+    }
+
+    /// Add an element, returning false if it was already present.  Triggers processing
+    /// if the element was added.
+    pub fn insert(&self, pc: &mut ProcessingContext, value: T) -> bool {
+        let mut self2 = self.0.mut_.borrow_mut();
+        if self2.value.contains(&value) {
+            return false;
+        }
+        self.touch_(&mut self2, pc, &value);
+        self2.value.insert(value);
+        return true;
+    }
+
+    /// Remove an element, returning false if it wasn't present.  Triggers processing if
+    /// the element was removed.
+    pub fn remove(&self, pc: &mut ProcessingContext, value: &T) -> bool {
+        let mut self2 = self.0.mut_.borrow_mut();
+        if !self2.value.contains(value) {
+            return false;
+        }
+        self.touch_(&mut self2, pc, value);
+        self2.value.remove(value);
+        return true;
+    }
+
+    /// Clears the collection; triggers processing.
+    pub fn clear(&self, pc: &mut ProcessingContext) {
+        let mut self2 = self.0.mut_.borrow_mut();
+        let values = self2.value.iter().cloned().collect::<Vec<_>>();
+        for value in &values {
+            self.touch_(&mut self2, pc, value);
+        }
+        self2.value.clear();
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        return self.0.mut_.borrow().value.contains(value);
+    }
+
+    /// The current state of the set.  A `Deref` wrapper around the internal
+    /// `HashSet`.
+    ///
+    /// Borrows the set, must be released before calling other mutate methods.
+    pub fn borrow_values<'a>(&'a self) -> SetValuesRef<'a, T> {
+        return SetValuesRef(self.0.mut_.borrow());
+    }
+
+    /// The net changes during the current event handling.  A `Deref` wrapper around a
+    /// map of each modified element to whether it was present before the event, with
+    /// methods to get added and removed elements.
+    ///
+    /// Borrows the set, must be released before calling other mutate methods.
+    pub fn borrow_changes<'a>(&'a self) -> SetChangesRef<'a, T> {
+        return SetChangesRef(self.0.mut_.borrow());
+    }
+}
+
+impl<T: Clone + Eq + Hash + 'static> IntoValue for Set<T> {
+    fn into_value(&self) -> Value {
+        return Value(self.0.clone());
+    }
+}
+
+impl<T: Clone + Eq + Hash + 'static> WeakSet<T> {
+    pub fn upgrade(&self) -> Option<Set<T>> {
+        return Some(Set(self.0.upgrade()?));
+    }
+}
+
+pub struct SetValuesRef<'a, T: Clone + Eq + Hash + 'static>(Ref<'a, SetMut_<T>>);
+
+impl<'a, T: Clone + Eq + Hash + 'static> Deref for SetValuesRef<'a, T> {
+    type Target = HashSet<T>;
+
+    fn deref(&self) -> &Self::Target {
+        return &self.0.value;
+    }
+}
+
+pub struct SetChangesRef<'a, T: Clone + Eq + Hash + 'static>(Ref<'a, SetMut_<T>>);
+
+impl<'a, T: Clone + Eq + Hash + 'static> SetChangesRef<'a, T> {
+    /// Elements that weren't present before the event but are now.
+    pub fn added(&self) -> impl Iterator<Item = &T> {
+        return self.0.old.iter().filter(|(v, was)| !**was && self.0.value.contains(*v)).map(|(v, _)| v);
+    }
+
+    /// Elements that were present before the event but aren't now.
+    pub fn removed(&self) -> impl Iterator<Item = &T> {
+        return self.0.old.iter().filter(|(v, was)| **was && !self.0.value.contains(*v)).map(|(v, _)| v);
+    }
+}
+
+impl<'a, T: Clone + Eq + Hash + 'static> Deref for SetChangesRef<'a, T> {
+    type Target = HashMap<T, bool>;
+
+    fn deref(&self) -> &Self::Target {
+        return &self.0.old;
+    }
+}
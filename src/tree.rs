@@ -0,0 +1,316 @@
+use std::{
+    rc::{
+        Rc,
+        Weak,
+    },
+    cell::{
+        RefCell,
+        Ref,
+    },
+    ops::Deref,
+};
+use crate::{
+    core::{
+        ValueTrait,
+        ProcessingContext,
+        Cleanup,
+        Link_,
+        IntoValue,
+        Value,
+    },
+    Link,
+};
+
+/// A node in a `Tree`, with its subtree.
+#[derive(Clone, PartialEq, Debug)]
+pub struct TreeNode<T> {
+    pub value: T,
+    pub children: std::vec::Vec<TreeNode<T>>,
+}
+
+impl<T> TreeNode<T> {
+    pub fn new(value: T, children: std::vec::Vec<TreeNode<T>>) -> Self {
+        return TreeNode {
+            value: value,
+            children: children,
+        };
+    }
+
+    pub fn leaf(value: T) -> Self {
+        return TreeNode::new(value, vec![]);
+    }
+}
+
+/// Get the node at `path`, where each element of the path is the index within the
+/// previous node's children (the first element is the index in `roots`).
+pub fn node_at<'a, T>(roots: &'a [TreeNode<T>], path: &[usize]) -> Option<&'a TreeNode<T>> {
+    let (first, rest) = path.split_first()?;
+    let mut at = roots.get(*first)?;
+    for i in rest {
+        at = at.children.get(*i)?;
+    }
+    return Some(at);
+}
+
+/// Get the list of siblings containing the node at `path` (the children of its
+/// parent).
+fn siblings_mut<'a, T>(
+    roots: &'a mut std::vec::Vec<TreeNode<T>>,
+    path: &[usize],
+) -> &'a mut std::vec::Vec<TreeNode<T>> {
+    let (_, parent) = path.split_last().expect("Tree paths must not be empty");
+    let mut at = roots;
+    for i in parent {
+        at = &mut at[*i].children;
+    }
+    return at;
+}
+
+/// Whether a node can be inserted so that it ends up at `path` (its parent exists and
+/// the index is at most the number of siblings).
+fn can_insert_at<T>(roots: &[TreeNode<T>], path: &[usize]) -> bool {
+    let Some((index, parent)) = path.split_last() else {
+        return false;
+    };
+    let siblings = if parent.is_empty() {
+        roots
+    } else {
+        match node_at(roots, parent) {
+            Some(n) => &n.children,
+            None => return false,
+        }
+    };
+    return *index <= siblings.len();
+}
+
+/// A single modification to a tree.  Paths in each change are relative to the state
+/// of the tree after the previous change was applied.
+pub enum TreeChange<T: Clone> {
+    /// `node` (with its subtree) was inserted so that it's at `path`.
+    Insert {
+        path: std::vec::Vec<usize>,
+        node: TreeNode<T>,
+    },
+    /// The node at `path` and its subtree were removed.
+    Remove {
+        path: std::vec::Vec<usize>,
+    },
+    /// The node at `from` and its subtree were removed and reinserted so that it ends
+    /// up at `to`.  `to` is a path in the tree after the removal.
+    Move {
+        from: std::vec::Vec<usize>,
+        to: std::vec::Vec<usize>,
+    },
+    /// The value of the node at `path` was replaced with `value`, its children are
+    /// unchanged.
+    Update {
+        path: std::vec::Vec<usize>,
+        value: T,
+    },
+}
+
+impl<T: Clone> TreeChange<T> {
+    /// Apply this change to a copy of the tree.
+    pub fn apply_to(&self, roots: &mut std::vec::Vec<TreeNode<T>>) {
+        match self {
+            TreeChange::Insert { path, node } => {
+                siblings_mut(roots, path).insert(*path.last().unwrap(), node.clone());
+            },
+            TreeChange::Remove { path } => {
+                siblings_mut(roots, path).remove(*path.last().unwrap());
+            },
+            TreeChange::Move { from, to } => {
+                let node = siblings_mut(roots, from).remove(*from.last().unwrap());
+                siblings_mut(roots, to).insert(*to.last().unwrap(), node);
+            },
+            TreeChange::Update { path, value } => {
+                siblings_mut(roots, path)[*path.last().unwrap()].value = value.clone();
+            },
+        }
+    }
+}
+
+pub struct TreeMut_<T: Clone> {
+    value: std::vec::Vec<TreeNode<T>>,
+    changes: std::vec::Vec<TreeChange<T>>,
+    next: std::vec::Vec<Weak<Link_>>,
+}
+
+impl<T: Clone> TreeMut_<T> {
+    fn next(&mut self) -> Vec<crate::Link> {
+        let mut out = vec![];
+        out.reserve(self.next.len());
+        self.next.retain_mut(|e| {
+            match e.upgrade() {
+                Some(e) => {
+                    out.push(Link(e.clone()));
+                    return true;
+                },
+                None => {
+                    return false;
+                },
+            }
+        });
+        return out;
+    }
+}
+
+struct Tree_<T: Clone> {
+    mut_: RefCell<TreeMut_<T>>,
+}
+
+impl<T: Clone> ValueTrait for Tree_<T> {
+    fn next_links(&self) -> Vec<crate::Link> {
+        return self.mut_.borrow_mut().next();
+    }
+}
+
+impl<T: Clone> Cleanup for Tree_<T> {
+    fn clean(&self) {
+        self.mut_.borrow_mut().changes.clear();
+    }
+}
+
+/// A value that manages a forest of nodes, each with a value and ordered children.
+/// Nodes are addressed by paths of child indexes starting from the roots (see
+/// `node_at`).
+#[derive(Clone)]
+pub struct Tree<T: Clone>(Rc<Tree_<T>>);
+
+#[derive(Clone)]
+pub struct WeakTree<T: Clone>(Weak<Tree_<T>>);
+
+impl<T: Clone + 'static> Tree<T> {
+    pub fn new(roots: std::vec::Vec<TreeNode<T>>) -> Self {
+        return Tree(Rc::new(Tree_ { mut_: RefCell::new(TreeMut_ {
+            value: roots,
+            changes: vec![],
+            next: vec![],
+        }) }));
+    }
+
+    /// Used internally by the `link!` macro to establish graph edges between an input
+    /// value and the link.
+    pub fn add_next(&self, link: &Link) {
+        self.0.mut_.borrow_mut().next.push(Rc::downgrade(&link.0));
+    }
+
+    /// Get a weak reference to the tree.
+    pub fn weak(&self) -> WeakTree<T> {
+        return WeakTree(Rc::downgrade(&self.0));
+    }
+
+    fn change_(&self, self2: &mut TreeMut_<T>, pc: &mut ProcessingContext, change: TreeChange<T>) {
+        let first_change = self2.changes.is_empty();
+        self2.changes.push(change);
+        if first_change {
+            pc.1.cleanup.push(self.0.clone());
+            pc.1.mark_changed(self2.next());
+        }
+    }
+
+    /// Insert a node and its subtree so that it ends up at `path`; triggers
+    /// processing.
+    pub fn insert(&self, pc: &mut ProcessingContext, path: std::vec::Vec<usize>, node: TreeNode<T>) {
+        let mut self2 = self.0.mut_.borrow_mut();
+        siblings_mut(&mut self2.value, &path).insert(*path.last().unwrap(), node.clone());
+        self.change_(&mut self2, pc, TreeChange::Insert {
+            path: path,
+            node: node,
+        });
+    }
+
+    /// Remove and return the node at `path` with its subtree; triggers processing.
+    pub fn remove(&self, pc: &mut ProcessingContext, path: std::vec::Vec<usize>) -> TreeNode<T> {
+        let mut self2 = self.0.mut_.borrow_mut();
+        let out = siblings_mut(&mut self2.value, &path).remove(*path.last().unwrap());
+        self.change_(&mut self2, pc, TreeChange::Remove { path: path });
+        return out;
+    }
+
+    /// Move the node at `from` with its subtree so that it ends up at `to`, where `to`
+    /// is a path in the tree after the node is removed.  Triggers processing.
+    pub fn move_subtree(&self, pc: &mut ProcessingContext, from: std::vec::Vec<usize>, to: std::vec::Vec<usize>) {
+        if from == to {
+            return;
+        }
+        let mut self2 = self.0.mut_.borrow_mut();
+        let node = siblings_mut(&mut self2.value, &from).remove(*from.last().unwrap());
+        if !can_insert_at(&self2.value, &to) {
+            siblings_mut(&mut self2.value, &from).insert(*from.last().unwrap(), node);
+            panic!("move destination {:?} isn't a valid path after removing {:?}", to, from);
+        }
+        siblings_mut(&mut self2.value, &to).insert(*to.last().unwrap(), node);
+        self.change_(&mut self2, pc, TreeChange::Move {
+            from: from,
+            to: to,
+        });
+    }
+
+    /// Replace the value of the node at `path`, keeping its children, and return the
+    /// old value; triggers processing.
+    pub fn set(&self, pc: &mut ProcessingContext, path: std::vec::Vec<usize>, value: T) -> T {
+        let mut self2 = self.0.mut_.borrow_mut();
+        let index = *path.last().unwrap();
+        let out = std::mem::replace(&mut siblings_mut(&mut self2.value, &path)[index].value, value.clone());
+        self.change_(&mut self2, pc, TreeChange::Update {
+            path: path,
+            value: value,
+        });
+        return out;
+    }
+
+    /// Get a copy of the value of the node at `path`.
+    pub fn get(&self, path: &[usize]) -> Option<T> {
+        return node_at(&self.0.mut_.borrow().value, path).map(|n| n.value.clone());
+    }
+
+    /// The current state of the tree.  A `Deref` wrapper around the internal `Vec` of
+    /// root nodes.
+    ///
+    /// Borrows the tree, must be released before calling other mutate methods.
+    pub fn borrow_values<'a>(&'a self) -> TreeValuesRef<'a, T> {
+        return TreeValuesRef(self.0.mut_.borrow());
+    }
+
+    /// Any changes during the current event handling that occurred to value to get it
+    /// to its current state.  You can replay them on a copy of the tree to synchronize
+    /// it (see `TreeChange::apply_to`).  A `Deref` wrapper around an internal `Vec`.
+    ///
+    /// Borrows the tree, must be released before calling other mutate methods.
+    pub fn borrow_changes<'a>(&'a self) -> TreeChangesRef<'a, T> {
+        return TreeChangesRef(self.0.mut_.borrow());
+    }
+}
+
+impl<T: Clone + 'static> IntoValue for Tree<T> {
+    fn into_value(&self) -> Value {
+        return Value(self.0.clone());
+    }
+}
+
+impl<T: Clone + 'static> WeakTree<T> {
+    pub fn upgrade(&self) -> Option<Tree<T>> {
+        return Some(Tree(self.0.upgrade()?));
+    }
+}
+
+pub struct TreeValuesRef<'a, T: Clone + 'static>(Ref<'a, TreeMut_<T>>);
+
+impl<'a, T: Clone + 'static> Deref for TreeValuesRef<'a, T> {
+    type Target = Vec<TreeNode<T>>;
+
+    fn deref(&self) -> &Self::Target {
+        return &self.0.value;
+    }
+}
+
+pub struct TreeChangesRef<'a, T: Clone + 'static>(Ref<'a, TreeMut_<T>>);
+
+impl<'a, T: Clone + 'static> Deref for TreeChangesRef<'a, T> {
+    type Target = Vec<TreeChange<T>>;
+
+    fn deref(&self) -> &Self::Target {
+        return &self.0.changes;
+    }
+}
@@ -321,7 +321,17 @@ fn basic_list() {
         let b = lunk::List::new(vec![]);
         let _link = lunk::link!((pc = pc), (a = a.clone()), (b = b.clone()), () {
             for change in a.borrow_changes().iter() {
-                b.splice(pc, change.offset, change.remove, change.add.iter().map(|x| x + 5).collect());
+                match change {
+                    lunk::list::Change::Splice { offset, remove, add, .. } => {
+                        b.splice(pc, *offset, *remove, add.iter().map(|x| x + 5).collect());
+                    },
+                    lunk::list::Change::Replace { offset, value, .. } => {
+                        b.set(pc, *offset, value + 5);
+                    },
+                    lunk::list::Change::Move { from, to } => {
+                        b.move_item(pc, *from, *to);
+                    },
+                }
             }
         });
         a.splice(pc, 0, 0, vec![46]);
@@ -342,7 +352,17 @@ fn basic_list2x() {
         let b = lunk::List::new(vec![]);
         let _link = lunk::link!((pc = pc), (a = a.clone()), (b = b.clone()), () {
             for change in a.borrow_changes().iter() {
-                b.splice(pc, change.offset, change.remove, change.add.iter().map(|x| x + 5).collect());
+                match change {
+                    lunk::list::Change::Splice { offset, remove, add, .. } => {
+                        b.splice(pc, *offset, *remove, add.iter().map(|x| x + 5).collect());
+                    },
+                    lunk::list::Change::Replace { offset, value, .. } => {
+                        b.set(pc, *offset, value + 5);
+                    },
+                    lunk::list::Change::Move { from, to } => {
+                        b.move_item(pc, *from, *to);
+                    },
+                }
             }
         });
         a.splice(pc, 0, 0, vec![46]);
@@ -357,6 +377,1399 @@ fn basic_list2x() {
     assert_eq!(store_b.unwrap().borrow_values()[0], 17);
 }
 
+#[test]
+fn list_element_ops() {
+    let eg = lunk::EventGraph::new();
+    let mut store_a = None;
+    let mut store_b = None;
+    let mut store_other = None;
+    eg.event(|pc| {
+        let a = lunk::List::new(vec![1, 2, 3, 4]);
+        let b = lunk::List::new(vec![]);
+        let _link = lunk::link!((pc = pc), (a = a.clone()), (b = b.clone()), () {
+            b.clear(pc);
+            b.extend(pc, a.borrow_values().clone());
+        });
+        store_a = Some(a);
+        store_b = Some(b);
+        store_other = Some(_link);
+    });
+    let a = store_a.unwrap();
+    let b = store_b.unwrap();
+    eg.event(|pc| {
+        a.insert(pc, 1, 10);
+        assert_eq!(a.remove(pc, 0), 1);
+        assert_eq!(a.set(pc, 0, 11), 10);
+        a.swap(pc, 0, 3);
+        a.move_item(pc, 0, 2);
+    });
+    assert_eq!(a.borrow_values().clone(), vec![2, 3, 4, 11]);
+    assert_eq!(b.borrow_values().clone(), vec![2, 3, 4, 11]);
+}
+
+#[test]
+fn list_element_ops_changes() {
+    let eg = lunk::EventGraph::new();
+    let mut store_a = None;
+    let mut store_b = None;
+    let mut store_other = None;
+    eg.event(|pc| {
+        let a = lunk::List::new(vec![1, 2, 3, 4]);
+        let b = lunk::List::new(vec![6, 7, 8, 9]);
+        let _link = lunk::link!((pc = pc), (a = a.clone()), (b = b.clone()), () {
+            for change in a.borrow_changes().iter() {
+                match change {
+                    lunk::list::Change::Splice { offset, remove, add, .. } => {
+                        b.splice(pc, *offset, *remove, add.iter().map(|x| x + 5).collect());
+                    },
+                    lunk::list::Change::Replace { offset, value, .. } => {
+                        b.set(pc, *offset, value + 5);
+                    },
+                    lunk::list::Change::Move { from, to } => {
+                        b.move_item(pc, *from, *to);
+                    },
+                }
+            }
+        });
+        store_a = Some(a);
+        store_b = Some(b);
+        store_other = Some(_link);
+    });
+    eg.event(|pc| {
+        let a = store_a.as_ref().unwrap();
+        a.swap(pc, 3, 0);
+        a.swap(pc, 1, 2);
+        a.set(pc, 1, 20);
+        a.move_item(pc, 3, 0);
+    });
+    assert_eq!(store_a.unwrap().borrow_values().clone(), vec![1, 4, 20, 2]);
+    assert_eq!(store_b.unwrap().borrow_values().clone(), vec![6, 9, 25, 7]);
+}
+
+#[test]
+fn list_move_out_of_range() {
+    use lunk::list::ChangeSink;
+    let eg = lunk::EventGraph::new();
+    let runs = Rc::new(Cell::new(0));
+    let (a, _link) = eg.event(|pc| {
+        let a = lunk::List::new(vec![1, 2, 3]);
+        let _link = lunk::link!((_pc = pc), (_a = a.clone()), (), (runs = runs.clone()) {
+            runs.set(runs.get() + 1);
+        });
+        return (a, _link);
+    }).unwrap();
+    eg.event(|pc| {
+        let moved = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| a.move_item(pc, 0, 3)));
+        assert!(moved.is_err());
+        let swapped = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| a.swap(pc, 3, 0)));
+        assert!(swapped.is_err());
+    });
+    assert_eq!(a.borrow_values().clone(), vec![1, 2, 3]);
+    assert_eq!(runs.get(), 1);
+
+    let mut v = vec![1, 2, 3];
+    assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| v.move_item(0, 3))).is_err());
+    assert_eq!(v, vec![1, 2, 3]);
+    let mut v = std::collections::VecDeque::from(vec![1, 2, 3]);
+    assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| v.move_item(0, 3))).is_err());
+    assert_eq!(v, vec![1, 2, 3]);
+}
+
+#[test]
+fn list_keep_removed() {
+    let eg = lunk::EventGraph::new();
+    let removed = Rc::new(RefCell::new(vec![]));
+    let mut store_a = None;
+    let mut store_other = None;
+    eg.event(|pc| {
+        let a = lunk::List::new_keep_removed(vec![1, 2, 3, 4]);
+        let _link = lunk::link!((_pc = pc), (a = a.clone()), (), (removed = removed.clone()) {
+            for change in a.borrow_changes().iter() {
+                match change {
+                    lunk::list::Change::Splice { removed: Some(r), .. } => {
+                        removed.borrow_mut().extend(r.iter().cloned());
+                    },
+                    lunk::list::Change::Replace { old: Some(o), .. } => {
+                        removed.borrow_mut().push(*o);
+                    },
+                    _ => { },
+                }
+            }
+        });
+        store_a = Some(a);
+        store_other = Some(_link);
+    });
+    eg.event(|pc| {
+        let a = store_a.as_ref().unwrap();
+        a.splice(pc, 1, 2, vec![7]);
+        a.set(pc, 0, 8);
+        a.pop(pc);
+    });
+    assert_eq!(removed.borrow().clone(), vec![2, 3, 1, 4]);
+}
+
+fn mirror_list(pc: &mut lunk::ProcessingContext, a: &lunk::List<i32>, b: &lunk::List<i32>) -> lunk::Link {
+    return lunk::link!((pc = pc), (a = a.clone()), (b = b.clone()), () {
+        for change in a.borrow_changes().iter() {
+            match change {
+                lunk::list::Change::Splice { offset, remove, add, .. } => {
+                    b.splice(pc, *offset, *remove, add.clone());
+                },
+                lunk::list::Change::Replace { offset, value, .. } => {
+                    b.set(pc, *offset, *value);
+                },
+                lunk::list::Change::Move { from, to } => {
+                    b.move_item(pc, *from, *to);
+                },
+            }
+        }
+    });
+}
+
+/// A deterministic pseudo-random generator for randomized tests, returning values in
+/// `0..max`.
+fn lcg(seed: u32) -> impl FnMut(usize) -> usize {
+    let mut seed = seed;
+    return move |max: usize| {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        return (seed >> 16) as usize % max;
+    };
+}
+
+#[test]
+fn list_replace_all() {
+    let eg = lunk::EventGraph::new();
+    let (a, b, _link) = eg.event(|pc| {
+        let a = lunk::List::new(vec![1, 2, 3, 4, 5]);
+        let b = lunk::List::new(vec![1, 2, 3, 4, 5]);
+        let link = mirror_list(pc, &a, &b);
+        return (a, b, link);
+    }).unwrap();
+    let change_count = Rc::new(Cell::new(0));
+    let _count_link = eg.event(|pc| {
+        let count_link = lunk::link!((_pc = pc), (a = a.clone()), (), (change_count = change_count.clone()) {
+            change_count.set(a.borrow_changes().len());
+        });
+        a.replace_all(pc, vec![1, 3, 4, 6, 5, 7]);
+        return count_link;
+    }).unwrap();
+    assert_eq!(b.borrow_values().clone(), vec![1, 3, 4, 6, 5, 7]);
+    assert_eq!(change_count.get(), 3);
+}
+
+#[test]
+fn list_replace_all_random() {
+    let mut rand = lcg(7);
+    let eg = lunk::EventGraph::new();
+    let (a, b, _link) = eg.event(|pc| {
+        let a = lunk::List::new(vec![]);
+        let b = lunk::List::new(vec![]);
+        let link = mirror_list(pc, &a, &b);
+        return (a, b, link);
+    }).unwrap();
+    for _ in 0 .. 50 {
+        let len = rand(20);
+        let values = (0 .. len).map(|_| rand(6) as i32).collect::<Vec<_>>();
+        eg.event(|pc| {
+            a.replace_all(pc, values.clone());
+        });
+        assert_eq!(a.borrow_values().clone(), values);
+        assert_eq!(b.borrow_values().clone(), values);
+    }
+}
+
+#[test]
+fn list_replace_all_by() {
+    let eg = lunk::EventGraph::new();
+    let (a, b, _link) = eg.event(|pc| {
+        let a = lunk::List::new(vec![10, 21, 30]);
+        let b = lunk::List::new(vec![10, 21, 30]);
+        let link = mirror_list(pc, &a, &b);
+        return (a, b, link);
+    }).unwrap();
+    eg.event(|pc| {
+        a.replace_all_by(pc, vec![11, 30, 42], |v| *v / 10, |a, b| a == b);
+    });
+    assert_eq!(b.borrow_values().clone(), vec![11, 30, 42]);
+
+    // Only matched rows that differ are replaced
+    let rows = |texts: [&str; 3]| texts.iter().enumerate().map(|(i, t)| (i, t.to_string())).collect::<Vec<_>>();
+    let changes = Rc::new(RefCell::new(vec![]));
+    let (a, _link) = eg.event(|pc| {
+        let a = lunk::List::new(rows(["a", "b", "c"]));
+        let link = lunk::link!((_pc = pc), (a = a.clone()), (), (changes = changes.clone()) {
+            changes.borrow_mut().push(a.borrow_changes().len());
+        });
+        return (a, link);
+    }).unwrap();
+    changes.borrow_mut().clear();
+    eg.event(|pc| {
+        a.replace_all_by(pc, rows(["a", "b", "c"]), |r| r.0, |a, b| a.1 == b.1);
+    });
+    assert_eq!(*changes.borrow(), Vec::<usize>::new());
+    eg.event(|pc| {
+        a.replace_all_by(pc, rows(["a", "x", "c"]), |r| r.0, |a, b| a.1 == b.1);
+    });
+    assert_eq!(*changes.borrow(), vec![1]);
+    assert_eq!(a.borrow_values().clone(), rows(["a", "x", "c"]));
+}
+
+#[test]
+fn list_map() {
+    let eg = lunk::EventGraph::new();
+    let calls = Rc::new(Cell::new(0));
+    let (a, b) = eg.event(|pc| {
+        let a = lunk::List::new(vec![1, 2]);
+        a.push(pc, 3);
+        let b = a.map(pc, {
+            let calls = calls.clone();
+            move |x| {
+                calls.set(calls.get() + 1);
+                return x * 10;
+            }
+        });
+        a.push(pc, 4);
+        return (a, b);
+    }).unwrap();
+    assert_eq!(b.borrow_values().clone(), vec![10, 20, 30, 40]);
+    assert_eq!(calls.get(), 4);
+    eg.event(|pc| {
+        a.remove(pc, 0);
+        a.insert(pc, 1, 5);
+        a.set(pc, 0, 6);
+        a.move_item(pc, 0, 3);
+    });
+    assert_eq!(a.borrow_values().clone(), vec![5, 3, 4, 6]);
+    assert_eq!(b.borrow_values().clone(), vec![50, 30, 40, 60]);
+    assert_eq!(calls.get(), 6);
+}
+
+#[test]
+fn list_map_chain() {
+    let eg = lunk::EventGraph::new();
+    let (a, c, _link, d) = eg.event(|pc| {
+        let a = lunk::List::new(vec![1, 2]);
+        let b = a.map(pc, |x| x + 1);
+        let c = b.map(pc, |x| x * 2);
+        let d = lunk::Prim::new(0);
+        let link = lunk::link!((pc = pc), (c = c.clone()), (d = d.clone()), () {
+            d.set(pc, c.borrow_values().iter().sum());
+        });
+        return (a, c, link, d);
+    }).unwrap();
+    assert_eq!(c.borrow_values().clone(), vec![4, 6]);
+    assert_eq!(*d.borrow(), 10);
+    eg.event(|pc| {
+        a.push(pc, 3);
+    });
+    assert_eq!(c.borrow_values().clone(), vec![4, 6, 8]);
+    assert_eq!(*d.borrow(), 18);
+}
+
+#[test]
+fn list_filter() {
+    let eg = lunk::EventGraph::new();
+    let (a, b) = eg.event(|pc| {
+        let a = lunk::List::new(vec![1, 2, 3, 4, 5, 6]);
+        let b = a.filter(pc, |x| x % 2 == 0);
+        return (a, b);
+    }).unwrap();
+    assert_eq!(b.borrow_values().clone(), vec![2, 4, 6]);
+    eg.event(|pc| {
+        a.splice(pc, 1, 2, vec![8, 9, 10]);
+        a.set(pc, 0, 12);
+        a.set(pc, 2, 11);
+        a.move_item(pc, 0, 5);
+        a.swap(pc, 0, 1);
+    });
+    let expected = a.borrow_values().iter().cloned().filter(|x| x % 2 == 0).collect::<Vec<_>>();
+    assert_eq!(b.borrow_values().clone(), expected);
+}
+
+#[test]
+fn list_filter_with() {
+    let eg = lunk::EventGraph::new();
+    let (a, search, b) = eg.event(|pc| {
+        let a = lunk::List::new(vec!["apple", "banana", "cherry", "avocado"]);
+        let search = lunk::Prim::new("a".to_string());
+        let b = a.filter_with(pc, &search, |search: &String, x: &&str| x.starts_with(search.as_str()));
+        return (a, search, b);
+    }).unwrap();
+    assert_eq!(b.borrow_values().clone(), vec!["apple", "avocado"]);
+    eg.event(|pc| {
+        search.set(pc, "b".to_string());
+    });
+    assert_eq!(b.borrow_values().clone(), vec!["banana"]);
+    eg.event(|pc| {
+        a.push(pc, "blueberry");
+        search.set(pc, "".to_string());
+    });
+    assert_eq!(b.borrow_values().clone(), vec!["apple", "banana", "cherry", "avocado", "blueberry"]);
+    eg.event(|pc| {
+        a.remove(pc, 0);
+    });
+    assert_eq!(b.borrow_values().clone(), vec!["banana", "cherry", "avocado", "blueberry"]);
+}
+
+#[test]
+fn list_sorted_by() {
+    let mut rand = lcg(3);
+    let eg = lunk::EventGraph::new();
+    let (a, b, mirror, _link) = eg.event(|pc| {
+        let a = lunk::List::new(vec![5, 3, 9, 1]);
+        let b = a.sorted_by(pc, |x: &i32, y: &i32| x.cmp(y));
+        let mirror = lunk::List::new(b.borrow_values().clone());
+        let link = mirror_list(pc, &b, &mirror);
+        return (a, b, mirror, link);
+    }).unwrap();
+    assert_eq!(b.borrow_values().clone(), vec![1, 3, 5, 9]);
+    for _ in 0 .. 50 {
+        eg.event(|pc| {
+            let len = a.borrow_values().len();
+            match rand(4) {
+                0 => {
+                    a.insert(pc, rand(len + 1), rand(20) as i32);
+                },
+                1 => if len > 0 {
+                    a.remove(pc, rand(len));
+                },
+                2 => if len > 0 {
+                    a.set(pc, rand(len), rand(20) as i32);
+                },
+                _ => if len > 0 {
+                    a.move_item(pc, rand(len), rand(len));
+                },
+            }
+        });
+        let mut expected = a.borrow_values().clone();
+        expected.sort();
+        assert_eq!(b.borrow_values().clone(), expected);
+        assert_eq!(mirror.borrow_values().clone(), expected);
+    }
+}
+
+#[test]
+fn list_aggregates() {
+    let eg = lunk::EventGraph::new();
+    let (a, len, sum, min, max, even, product) = eg.event(|pc| {
+        let a = lunk::List::new(vec![4, 2, 7]);
+        let len = a.len_prim(pc);
+        let sum = a.sum(pc);
+        let min = a.min(pc);
+        let max = a.max(pc);
+        let even = a.count_where(pc, |x| x % 2 == 0);
+        let product = a.fold(pc, 1, |acc, x| acc * x, |acc, x| acc / x);
+        return (a, len, sum, min, max, even, product);
+    }).unwrap();
+    let check = || {
+        let values = a.borrow_values().clone();
+        assert_eq!(len.get(), values.len());
+        assert_eq!(sum.get(), values.iter().sum::<i32>());
+        assert_eq!(min.get(), values.iter().min().cloned());
+        assert_eq!(max.get(), values.iter().max().cloned());
+        assert_eq!(even.get(), values.iter().filter(|x| *x % 2 == 0).count());
+        assert_eq!(product.get(), values.iter().product::<i32>());
+    };
+    check();
+    eg.event(|pc| {
+        a.push(pc, 1);
+        a.remove(pc, 2);
+
+        // Aggregates don't change what other consumers of the list see
+        match &a.borrow_changes()[1] {
+            lunk::list::Change::Splice { removed, .. } => assert!(removed.is_none()),
+            _ => panic!(),
+        }
+    });
+    check();
+    eg.event(|pc| {
+        a.set(pc, 1, 9);
+        a.swap(pc, 0, 2);
+    });
+    check();
+    eg.event(|pc| {
+        a.clear(pc);
+    });
+    check();
+    eg.event(|pc| {
+        a.extend(pc, vec![3, 3, 5]);
+    });
+    check();
+}
+
+#[test]
+fn list_compacted_changes() {
+    let eg = lunk::EventGraph::new();
+    let compacted = Rc::new(RefCell::new(vec![]));
+    let (a, _link) = eg.event(|pc| {
+        let a = lunk::List::new_keep_removed(vec![1, 2, 3]);
+        let link = lunk::link!((_pc = pc), (a = a.clone()), (), (compacted = compacted.clone()) {
+            *compacted.borrow_mut() = a.compacted_changes();
+        });
+        return (a, link);
+    }).unwrap();
+    eg.event(|pc| {
+        a.push(pc, 4);
+        a.pop(pc);
+    });
+    assert_eq!(compacted.borrow().len(), 0);
+    eg.event(|pc| {
+        a.push(pc, 4);
+        a.push(pc, 5);
+        a.insert(pc, 3, 6);
+        a.set(pc, 4, 7);
+    });
+    assert_eq!(compacted.borrow().len(), 1);
+    assert!(matches!(
+        &compacted.borrow()[0],
+        lunk::list::Change::Splice { offset: 3, remove: 0, add, .. } if *add == vec![6, 7, 5]
+    ));
+
+    // Randomized, replaying compacted changes from the pre-event state
+    let mut rand = lcg(11);
+    for _ in 0 .. 100 {
+        let before = a.borrow_values().clone();
+        compacted.borrow_mut().clear();
+        eg.event(|pc| {
+            for _ in 0 .. rand(6) {
+                let len = a.borrow_values().len();
+                match rand(5) {
+                    0 => a.push(pc, rand(100) as i32),
+                    1 => {
+                        a.pop(pc);
+                    },
+                    2 => {
+                        let offset = rand(len + 1);
+                        let remove = rand(len - offset + 1);
+                        a.splice(pc, offset, remove, (0 .. rand(3)).map(|_| rand(100) as i32).collect());
+                    },
+                    3 => if len > 0 {
+                        a.set(pc, rand(len), rand(100) as i32);
+                    },
+                    _ => if len > 0 {
+                        a.move_item(pc, rand(len), rand(len));
+                    },
+                }
+            }
+        });
+        let mut replay = before;
+        for change in compacted.borrow().iter() {
+            match change {
+                lunk::list::Change::Splice { offset, remove, add, removed } => {
+                    let actual = replay.splice(*offset .. *offset + *remove, add.clone()).collect::<Vec<_>>();
+                    assert_eq!(removed.as_ref(), Some(&actual));
+                },
+                lunk::list::Change::Replace { offset, value, old } => {
+                    assert_eq!(old.as_ref(), Some(&replay[*offset]));
+                    replay[*offset] = *value;
+                },
+                lunk::list::Change::Move { from, to } => {
+                    let v = replay.remove(*from);
+                    replay.insert(*to, v);
+                },
+            }
+        }
+        assert_eq!(replay, a.borrow_values().clone());
+    }
+}
+
+#[test]
+fn list_apply_changes() {
+    // Mock of DOM children, where nodes are expensive and have identity
+    #[derive(Default)]
+    struct Children {
+        nodes: Vec<(usize, String)>,
+        created: usize,
+    }
+
+    impl lunk::list::ChangeSink<String> for Children {
+        fn splice(&mut self, offset: usize, remove: usize, add: Vec<String>) {
+            let add = add.into_iter().map(|text| {
+                self.created += 1;
+                return (self.created, text);
+            }).collect::<Vec<_>>();
+            self.nodes.splice(offset .. offset + remove, add);
+        }
+
+        fn replace(&mut self, offset: usize, value: String) {
+            self.nodes[offset].1 = value;
+        }
+
+        fn move_item(&mut self, from: usize, to: usize) {
+            let node = self.nodes.remove(from);
+            self.nodes.insert(to, node);
+        }
+    }
+
+    let eg = lunk::EventGraph::new();
+    let vec = Rc::new(RefCell::new(vec![]));
+    let deque = Rc::new(RefCell::new(std::collections::VecDeque::new()));
+    let children = Rc::new(RefCell::new(Children::default()));
+    let (a, _link) = eg.event(|pc| {
+        let a = lunk::List::new(Vec::<i32>::new());
+        let link =
+            lunk::link!(
+                (_pc = pc),
+                (a = a.clone()),
+                (),
+                (vec = vec.clone(), deque = deque.clone(), children = children.clone()) {
+                    a.apply_changes(&mut *vec.borrow_mut(), |x| *x);
+                    a.apply_changes(&mut *deque.borrow_mut(), |x| *x * 2);
+                    a.apply_changes(&mut *children.borrow_mut(), |x| x.to_string());
+                }
+            );
+        return (a, link);
+    }).unwrap();
+    eg.event(|pc| {
+        a.extend(pc, vec![1, 2, 3, 4]);
+        a.remove(pc, 1);
+        a.set(pc, 0, 5);
+    });
+    eg.event(|pc| {
+        a.swap(pc, 0, 2);
+        a.insert(pc, 1, 6);
+    });
+    let expected = a.borrow_values().clone();
+    assert_eq!(expected, vec![4, 6, 3, 5]);
+    assert_eq!(*vec.borrow(), expected);
+    assert_eq!(deque.borrow().iter().cloned().collect::<Vec<_>>(), vec![8, 12, 6, 10]);
+    let children = children.borrow();
+    assert_eq!(children.nodes.iter().map(|n| n.1.clone()).collect::<Vec<_>>(), vec!["4", "6", "3", "5"]);
+    assert_eq!(children.created, 5);
+}
+
+#[test]
+fn list_cursor() {
+    let eg = lunk::EventGraph::new();
+    let a = eg.event(|_pc| lunk::List::new(vec![1, 2])).unwrap();
+    let cursor = a.cursor();
+    let mut mirror = a.borrow_values().clone();
+    eg.event(|pc| {
+        a.push(pc, 3);
+    });
+    eg.event(|pc| {
+        a.set(pc, 0, 4);
+        a.remove(pc, 1);
+    });
+    assert!(cursor.has_changes());
+    match cursor.drain() {
+        lunk::list::CursorDrain::Changes(changes) => {
+            assert_eq!(changes.len(), 3);
+            for change in changes {
+                change.apply_to(&mut mirror, |x| *x);
+            }
+        },
+        lunk::list::CursorDrain::Reset(_) => panic!(),
+    }
+    assert_eq!(mirror, vec![4, 3]);
+    assert!(!cursor.has_changes());
+
+    // Falling behind
+    cursor.set_max_pending(Some(2));
+    eg.event(|pc| {
+        a.push(pc, 5);
+        a.push(pc, 6);
+        a.push(pc, 7);
+    });
+    assert!(matches!(cursor.drain(), lunk::list::CursorDrain::Changes(changes) if changes.len() == 1));
+    for i in 0 .. 3 {
+        eg.event(|pc| {
+            a.insert(pc, 0, i);
+            a.push(pc, i);
+        });
+    }
+    match cursor.drain() {
+        lunk::list::CursorDrain::Changes(_) => panic!(),
+        lunk::list::CursorDrain::Reset(values) => assert_eq!(values, *a.borrow_values()),
+    }
+    assert!(!cursor.has_changes());
+}
+
+#[test]
+fn list_window() {
+    let mut rand = lcg(5);
+    let eg = lunk::EventGraph::new();
+    let fired = Rc::new(Cell::new(false));
+    let (a, range, window, mirror, _link) = eg.event(|pc| {
+        let a = lunk::List::new((0 .. 20).collect::<Vec<i32>>());
+        let range = lunk::Prim::new(5 .. 10);
+        let window = a.window(pc, &range);
+        let mirror = lunk::List::new(window.borrow_values().clone());
+        let link = mirror_list(pc, &window, &mirror);
+        let fired_link = lunk::link!((_pc = pc), (window = window.clone()), (), (fired = fired.clone()) {
+            _ = window;
+            fired.set(true);
+        });
+        return (a, range, window, mirror, (link, fired_link));
+    }).unwrap();
+    assert_eq!(window.borrow_values().clone(), vec![5, 6, 7, 8, 9]);
+    fired.set(false);
+    eg.event(|pc| {
+        a.splice(pc, 12, 3, vec![100]);
+        a.set(pc, 1, 101);
+    });
+    assert!(!fired.get());
+    eg.event(|pc| {
+        a.insert(pc, 0, 102);
+    });
+    assert!(fired.get());
+    assert_eq!(window.borrow_values().clone(), vec![4, 5, 6, 7, 8]);
+    let mut next = 1000;
+    for _ in 0 .. 500 {
+        eg.event(|pc| {
+            for _ in 0 .. rand(4) {
+                let len = a.borrow_values().len();
+                match rand(5) {
+                    0 => {
+                        let offset = rand(len + 1);
+                        let remove = rand((len - offset).min(8) + 1);
+                        let add = (0 .. rand(8)).map(|_| {
+                            next += 1;
+                            return next;
+                        }).collect();
+                        a.splice(pc, offset, remove, add);
+                    },
+                    1 if len > 0 => {
+                        next += 1;
+                        a.set(pc, rand(len), next);
+                    },
+                    2 if len > 0 => {
+                        a.move_item(pc, rand(len), rand(len));
+                    },
+                    3 => {
+                        let start = rand(30);
+                        range.set(pc, start .. start + rand(10));
+                    },
+                    _ => { },
+                }
+            }
+        });
+        let range = range.borrow().clone();
+        let values = a.borrow_values();
+        let expected = values.get(range.start .. range.end.min(values.len()).max(range.start)).unwrap_or_default();
+        assert_eq!(window.borrow_values().as_slice(), expected);
+        assert_eq!(mirror.borrow_values().as_slice(), expected);
+    }
+}
+
+#[test]
+fn list_bounded() {
+    let eg = lunk::EventGraph::new();
+    let (a, b, _link) = eg.event(|pc| {
+        let a = lunk::List::new_bounded(vec![1, 2, 3, 4], 3);
+        let b = lunk::List::new(a.borrow_values().clone());
+        let link = mirror_list(pc, &a, &b);
+        return (a, b, link);
+    }).unwrap();
+    assert_eq!(a.borrow_values().clone(), vec![2, 3, 4]);
+    eg.event(|pc| {
+        a.push(pc, 5);
+        a.extend(pc, vec![6, 7]);
+    });
+    assert_eq!(a.borrow_values().clone(), vec![5, 6, 7]);
+    assert_eq!(b.borrow_values().clone(), vec![5, 6, 7]);
+    eg.event(|pc| {
+        a.insert(pc, 1, 8);
+    });
+    assert_eq!(a.borrow_values().clone(), vec![8, 6, 7]);
+    assert_eq!(b.borrow_values().clone(), vec![8, 6, 7]);
+}
+
+#[test]
+fn list_bounded_replace() {
+    let eg = lunk::EventGraph::new();
+    let (a, b, _link) = eg.event(|pc| {
+        let a = lunk::List::new_bounded(vec![1, 2, 3], 3);
+        let b = lunk::List::new(a.borrow_values().clone());
+        let link = mirror_list(pc, &a, &b);
+        return (a, b, link);
+    }).unwrap();
+    eg.event(|pc| {
+        a.replace_all(pc, vec![0, 1, 9, 3]);
+    });
+    assert_eq!(a.borrow_values().clone(), vec![1, 9, 3]);
+    assert_eq!(b.borrow_values().clone(), vec![1, 9, 3]);
+    eg.event(|pc| {
+        a.replace_all_by(pc, vec![0, 1, 2, 3, 4], |v| *v, |a, b| a == b);
+    });
+    assert_eq!(a.borrow_values().clone(), vec![2, 3, 4]);
+    assert_eq!(b.borrow_values().clone(), vec![2, 3, 4]);
+    eg.event(|pc| {
+        a.mutate(pc, |v| {
+            v.insert(0, 7);
+            v.push(8);
+        });
+    });
+    assert_eq!(a.borrow_values().clone(), vec![3, 4, 8]);
+    assert_eq!(b.borrow_values().clone(), vec![3, 4, 8]);
+}
+
+#[test]
+fn list_concat() {
+    let eg = lunk::EventGraph::new();
+    let (a, b, concat, mirror, _link) = eg.event(|pc| {
+        let a = lunk::List::new(vec![1, 2]);
+        let b = lunk::List::new(vec![3]);
+        let concat = lunk::List::concat(pc, &[a.clone(), b.clone()]);
+        let mirror = lunk::List::new(concat.borrow_values().clone());
+        let link = mirror_list(pc, &concat, &mirror);
+        return (a, b, concat, mirror, link);
+    }).unwrap();
+    assert_eq!(concat.borrow_values().clone(), vec![1, 2, 3]);
+    eg.event(|pc| {
+        b.push(pc, 4);
+        a.remove(pc, 0);
+        a.push(pc, 5);
+    });
+    assert_eq!(concat.borrow_values().clone(), vec![2, 5, 3, 4]);
+    assert_eq!(mirror.borrow_values().clone(), vec![2, 5, 3, 4]);
+}
+
+#[test]
+fn list_flatten_random() {
+    let mut rand = lcg(13);
+    let eg = lunk::EventGraph::new();
+    let pool = (0 .. 6).map(|i| lunk::List::new(vec![i * 100])).collect::<Vec<_>>();
+    let (outer, flat, mirror, _link) = eg.event(|pc| {
+        let outer = lunk::List::new(vec![pool[0].clone(), pool[1].clone()]);
+        let flat = outer.flatten(pc);
+        let mirror = lunk::List::new(flat.borrow_values().clone());
+        let link = mirror_list(pc, &flat, &mirror);
+        return (outer, flat, mirror, link);
+    }).unwrap();
+    let mut next = 1000;
+    for _ in 0 .. 500 {
+        eg.event(|pc| {
+            for _ in 0 .. rand(4) {
+                let len = outer.borrow_values().len();
+                match rand(6) {
+                    0 => {
+                        let offset = rand(len + 1);
+                        let remove = rand((len - offset).min(2) + 1);
+                        let add = (0 .. rand(3)).map(|_| pool[rand(pool.len())].clone()).collect();
+                        outer.splice(pc, offset, remove, add);
+                    },
+                    1 if len > 0 => {
+                        outer.set(pc, rand(len), pool[rand(pool.len())].clone());
+                    },
+                    2 if len > 0 => {
+                        outer.move_item(pc, rand(len), rand(len));
+                    },
+                    _ => {
+                        let inner = &pool[rand(pool.len())];
+                        let inner_len = inner.borrow_values().len();
+                        next += 1;
+                        match rand(3) {
+                            0 => inner.insert(pc, rand(inner_len + 1), next),
+                            1 if inner_len > 0 => {
+                                inner.remove(pc, rand(inner_len));
+                            },
+                            _ if inner_len > 0 => inner.move_item(pc, rand(inner_len), rand(inner_len)),
+                            _ => { },
+                        }
+                    },
+                }
+            }
+        });
+        let expected =
+            outer.borrow_values().iter().flat_map(|l| l.borrow_values().clone()).collect::<Vec<_>>();
+        assert_eq!(flat.borrow_values().clone(), expected);
+        assert_eq!(mirror.borrow_values().clone(), expected);
+    }
+}
+
+#[test]
+fn list_group_by() {
+    let eg = lunk::EventGraph::new();
+    let headers = Rc::new(RefCell::new(vec![]));
+    let (a, groups, _link) = eg.event(|pc| {
+        let a = lunk::List::new(vec![1, 2, 3, 4]);
+        let groups = a.group_by(pc, |v| v % 3);
+        headers.borrow_mut().extend(groups.borrow_values().keys().cloned());
+        headers.borrow_mut().sort();
+        let link = lunk::link!((_pc = pc), (groups = groups.clone()), (), (headers = headers.clone()) {
+            let changes = groups.borrow_changes();
+            let mut headers = headers.borrow_mut();
+            headers.retain(|k| !changes.removed().any(|(k2, _)| k2 == k));
+            headers.extend(changes.inserted().map(|(k, _)| *k));
+            headers.sort();
+        });
+        return (a, groups, link);
+    }).unwrap();
+    assert_eq!(groups.get(&1).unwrap().borrow_values().clone(), vec![1, 4]);
+    assert_eq!(groups.get(&0).unwrap().borrow_values().clone(), vec![3]);
+    eg.event(|pc| {
+        a.remove(pc, 2);
+        a.push(pc, 7);
+        a.set(pc, 1, 5);
+    });
+    assert!(groups.get(&0).is_none());
+    assert_eq!(groups.get(&1).unwrap().borrow_values().clone(), vec![1, 4, 7]);
+    assert_eq!(groups.get(&2).unwrap().borrow_values().clone(), vec![5]);
+    assert_eq!(headers.borrow().clone(), vec![1, 2]);
+    eg.event(|pc| {
+        a.push(pc, 9);
+        a.move_item(pc, 3, 0);
+    });
+    assert_eq!(groups.get(&1).unwrap().borrow_values().clone(), vec![7, 1, 4]);
+    assert_eq!(headers.borrow().clone(), vec![0, 1, 2]);
+}
+
+#[test]
+fn list_group_by_random() {
+    let mut rand = lcg(17);
+    let eg = lunk::EventGraph::new();
+    let (a, groups) = eg.event(|pc| {
+        let a = lunk::List::new(vec![]);
+        let groups = a.group_by(pc, |v: &usize| v % 4);
+        return (a, groups);
+    }).unwrap();
+    for _ in 0 .. 500 {
+        eg.event(|pc| {
+            for _ in 0 .. rand(4) {
+                let len = a.borrow_values().len();
+                match rand(3) {
+                    0 => {
+                        let offset = rand(len + 1);
+                        let remove = rand((len - offset).min(4) + 1);
+                        let add = (0 .. rand(4)).map(|_| rand(100)).collect();
+                        a.splice(pc, offset, remove, add);
+                    },
+                    1 if len > 0 => {
+                        a.set(pc, rand(len), rand(100));
+                    },
+                    2 if len > 0 => {
+                        a.move_item(pc, rand(len), rand(len));
+                    },
+                    _ => { },
+                }
+            }
+        });
+        let values = a.borrow_values();
+        for k in 0 .. 4 {
+            let expected = values.iter().cloned().filter(|v| v % 4 == k).collect::<Vec<_>>();
+            match groups.get(&k) {
+                Some(group) => assert_eq!(group.borrow_values().clone(), expected),
+                None => assert!(expected.is_empty()),
+            }
+        }
+    }
+}
+
+#[test]
+fn list_mutate() {
+    let eg = lunk::EventGraph::new();
+    let (a, b, _link) = eg.event(|pc| {
+        let a = lunk::List::new(vec![5, 1, 4, 1, 3, 8]);
+        let b = lunk::List::new(a.borrow_values().clone());
+        let link = mirror_list(pc, &a, &b);
+        return (a, b, link);
+    }).unwrap();
+    eg.event(|pc| {
+        a.mutate(pc, |v| v.retain(|x| *x != 1));
+        assert_eq!(a.borrow_changes().len(), 2);
+    });
+    assert_eq!(a.borrow_values().clone(), vec![5, 4, 3, 8]);
+    assert_eq!(b.borrow_values().clone(), vec![5, 4, 3, 8]);
+    eg.event(|pc| {
+        a.mutate_replace(pc, |v| v.sort());
+        assert_eq!(a.borrow_changes().len(), 1);
+    });
+    assert_eq!(b.borrow_values().clone(), vec![3, 4, 5, 8]);
+    eg.event(|pc| {
+        let len = a.mutate(pc, |v| v.len());
+        assert_eq!(len, 4);
+        assert_eq!(a.borrow_changes().len(), 0);
+    });
+}
+
+#[test]
+fn list_index_by() {
+    let mut rand = lcg(19);
+    let eg = lunk::EventGraph::new();
+    let (a, index) = eg.event(|pc| {
+        let a = lunk::List::new(vec![(1, "a"), (2, "b"), (1, "c")]);
+        let index = a.index_by(pc, |v| v.0);
+        return (a, index);
+    }).unwrap();
+    assert_eq!(index.get(&2), Some(1));
+    assert_eq!(index.get_all(&1), vec![0, 2]);
+    assert_eq!(index.get(&3), None);
+    for _ in 0 .. 500 {
+        eg.event(|pc| {
+            for _ in 0 .. rand(4) {
+                let len = a.borrow_values().len();
+                match rand(3) {
+                    0 => {
+                        let offset = rand(len + 1);
+                        let remove = rand((len - offset).min(4) + 1);
+                        let add = (0 .. rand(4)).map(|_| (rand(10), "x")).collect();
+                        a.splice(pc, offset, remove, add);
+                    },
+                    1 if len > 0 => {
+                        a.set(pc, rand(len), (rand(10), "y"));
+                    },
+                    2 if len > 0 => {
+                        a.move_item(pc, rand(len), rand(len));
+                    },
+                    _ => { },
+                }
+            }
+        });
+        let values = a.borrow_values();
+        for k in 0 .. 10 {
+            let expected =
+                values.iter().enumerate().filter(|(_, v)| v.0 == k).map(|(i, _)| i).collect::<Vec<_>>();
+            assert_eq!(index.get_all(&k), expected);
+        }
+    }
+}
+
+#[test]
+fn keyed_list() {
+    let eg = lunk::EventGraph::new();
+    let (a, b, _link) = eg.event(|pc| {
+        let a = lunk::KeyedList::new_keep_values(vec![(1, "a"), (2, "b"), (3, "c")]);
+        let b = Rc::new(RefCell::new(vec![(1, "a"), (2, "b"), (3, "c")]));
+        let link = lunk::link!((_pc = pc), (a = a.clone()), (), (b = b.clone()) {
+            let mut b = b.borrow_mut();
+            for change in a.borrow_changes().iter() {
+                match change {
+                    lunk::keyed_list::KeyedChange::Insert { key, index, value } => {
+                        b.insert(*index, (*key, value.unwrap()));
+                    },
+                    lunk::keyed_list::KeyedChange::Remove { key, index, old } => {
+                        assert_eq!(b.remove(*index), (*key, old.unwrap()));
+                    },
+                    lunk::keyed_list::KeyedChange::Move { key, from, to } => {
+                        let e = b.remove(*from);
+                        assert_eq!(e.0, *key);
+                        b.insert(*to, e);
+                    },
+                    lunk::keyed_list::KeyedChange::Update { key, value, old } => {
+                        let i = b.iter().position(|e| e.0 == *key).unwrap();
+                        assert_eq!(b[i].1, old.unwrap());
+                        b[i].1 = value.unwrap();
+                    },
+                }
+            }
+        });
+        return (a, b, link);
+    }).unwrap();
+    eg.event(|pc| {
+        a.upsert(pc, 1, "a");
+        a.upsert(pc, 2, "x");
+        a.upsert(pc, 4, "d");
+        a.insert(pc, 0, 5, "e");
+        assert_eq!(a.remove_key(pc, &3), Some("c"));
+        a.move_key(pc, &4, 1);
+        a.insert(pc, 3, 5, "f");
+    });
+    let expected = vec![(4, "d"), (1, "a"), (2, "x"), (5, "f")];
+    assert_eq!(a.borrow_values().iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>(), expected);
+    assert_eq!(*b.borrow(), expected);
+    assert_eq!(a.get(&4), Some("d"));
+
+    // Existing keys can't be moved past the end
+    eg.event(|pc| {
+        a.insert(pc, 4, 1, "g");
+        a.move_key(pc, &4, 10);
+    });
+    let expected = vec![(2, "x"), (5, "f"), (1, "g"), (4, "d")];
+    assert_eq!(a.borrow_values().iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>(), expected);
+    assert_eq!(*b.borrow(), expected);
+    eg.event(|pc| {
+        a.clear(pc);
+    });
+    assert!(b.borrow().is_empty());
+
+    // Values are only kept if requested
+    eg.event(|pc| {
+        let a = lunk::KeyedList::new(vec![(1, "a")]);
+        a.upsert(pc, 1, "b");
+        assert!(matches!(
+            a.borrow_changes()[0],
+            lunk::keyed_list::KeyedChange::Update { value: None, old: None, .. }
+        ));
+    });
+}
+
+#[test]
+fn map() {
+    let eg = lunk::EventGraph::new();
+    let changes = Rc::new(RefCell::new((vec![], vec![], vec![])));
+    let (a, _link) = eg.event(|pc| {
+        let a = lunk::Map::new([(1, "a".to_string()), (2, "b".to_string())].into_iter().collect());
+        let link = lunk::link!((_pc = pc), (a = a.clone()), (), (changes = changes.clone()) {
+            let a_changes = a.borrow_changes();
+            let mut changes = changes.borrow_mut();
+            changes.0 = a_changes.inserted().map(|(k, v)| (*k, v.clone())).collect();
+            changes.1 = a_changes.removed().map(|(k, v)| (*k, v.clone())).collect();
+            changes.2 = a_changes.updated().map(|(k, old, v)| (*k, old.clone(), v.clone())).collect();
+            changes.0.sort();
+            changes.1.sort();
+            changes.2.sort();
+        });
+        return (a, link);
+    }).unwrap();
+    eg.event(|pc| {
+        a.insert(pc, 3, "c".to_string());
+        a.insert(pc, 4, "d".to_string());
+        a.remove(pc, &4);
+        a.remove(pc, &1);
+        assert!(a.modify(pc, &2, |v| v.push('x')));
+        assert!(!a.modify(pc, &5, |v| v.push('x')));
+        assert_eq!(a.insert(pc, 2, "e".to_string()), Some("bx".to_string()));
+    });
+    assert_eq!(changes.borrow().0, vec![(3, "c".to_string())]);
+    assert_eq!(changes.borrow().1, vec![(1, "a".to_string())]);
+    assert_eq!(changes.borrow().2, vec![(2, "b".to_string(), "e".to_string())]);
+    assert_eq!(a.get(&2), Some("e".to_string()));
+    eg.event(|pc| {
+        a.clear(pc);
+    });
+    assert_eq!(changes.borrow().0, vec![]);
+    assert_eq!(changes.borrow().1, vec![(2, "e".to_string()), (3, "c".to_string())]);
+    assert!(a.borrow_values().is_empty());
+}
+
+#[test]
+fn set() {
+    let eg = lunk::EventGraph::new();
+    let changes = Rc::new(RefCell::new((vec![], vec![])));
+    let (a, _link) = eg.event(|pc| {
+        let a = lunk::Set::new([1, 2, 3].into_iter().collect());
+        let link = lunk::link!((_pc = pc), (a = a.clone()), (), (changes = changes.clone()) {
+            let a_changes = a.borrow_changes();
+            let mut changes = changes.borrow_mut();
+            changes.0 = a_changes.added().cloned().collect();
+            changes.1 = a_changes.removed().cloned().collect();
+            changes.0.sort();
+            changes.1.sort();
+        });
+        return (a, link);
+    }).unwrap();
+    eg.event(|pc| {
+        assert!(a.insert(pc, 4));
+        assert!(!a.insert(pc, 1));
+        assert!(a.insert(pc, 5));
+        assert!(a.remove(pc, &5));
+        assert!(a.remove(pc, &2));
+        assert!(!a.remove(pc, &2));
+        assert!(a.remove(pc, &3));
+        assert!(a.insert(pc, 3));
+    });
+    assert_eq!(changes.borrow().0, vec![4]);
+    assert_eq!(changes.borrow().1, vec![2]);
+    assert!(a.contains(&4));
+    eg.event(|pc| {
+        a.clear(pc);
+    });
+    assert_eq!(changes.borrow().0, Vec::<i32>::new());
+    assert_eq!(changes.borrow().1, vec![1, 3, 4]);
+}
+
+#[test]
+fn tree() {
+    use lunk::TreeNode;
+
+    let eg = lunk::EventGraph::new();
+    let initial = vec![TreeNode::new("a", vec![TreeNode::leaf("a1"), TreeNode::leaf("a2")]), TreeNode::leaf("b")];
+    let mirror = Rc::new(RefCell::new(initial.clone()));
+    let (a, _link) = eg.event(|pc| {
+        let a = lunk::Tree::new(initial);
+        let link = lunk::link!((_pc = pc), (a = a.clone()), (), (mirror = mirror.clone()) {
+            for change in a.borrow_changes().iter() {
+                change.apply_to(&mut mirror.borrow_mut());
+            }
+        });
+        return (a, link);
+    }).unwrap();
+    eg.event(|pc| {
+        a.insert(pc, vec![1, 0], TreeNode::new("b1", vec![TreeNode::leaf("b1x")]));
+        a.set(pc, vec![0, 1], "a2'");
+        a.move_subtree(pc, vec![1, 0], vec![0, 0, 0]);
+        assert_eq!(a.remove(pc, vec![0, 1]), TreeNode::leaf("a2'"));
+        a.insert(pc, vec![2], TreeNode::leaf("c"));
+    });
+    let expected =
+        vec![
+            TreeNode::new(
+                "a",
+                vec![TreeNode::new("a1", vec![TreeNode::new("b1", vec![TreeNode::leaf("b1x")])])],
+            ),
+            TreeNode::leaf("b"),
+            TreeNode::leaf("c")
+        ];
+    assert_eq!(*a.borrow_values(), expected);
+    assert_eq!(*mirror.borrow(), expected);
+    assert_eq!(a.get(&[0, 0, 0, 0]), Some("b1x"));
+    assert_eq!(lunk::tree::node_at(&a.borrow_values(), &[0, 0, 1]), None);
+
+    // An invalid destination leaves the tree unchanged
+    eg.event(|pc| {
+        let moved = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            a.move_subtree(pc, vec![0], vec![5]);
+        }));
+        assert!(moved.is_err());
+        let moved = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            a.move_subtree(pc, vec![0], vec![1, 0, 0]);
+        }));
+        assert!(moved.is_err());
+    });
+    assert_eq!(*a.borrow_values(), expected);
+    assert_eq!(*mirror.borrow(), expected);
+}
+
+#[test]
+fn skip_unmodified_prim() {
+    let eg = lunk::EventGraph::new();
+    let b_runs = Rc::new(Cell::new(0));
+    let ab_runs = Rc::new(Cell::new(0));
+    let (a, b, _links) = eg.event(|pc| {
+        let a = lunk::Prim::new(0);
+        let b = lunk::Prim::new(0);
+
+        // Only passes even values through
+        let l1 = lunk::link!((pc = pc), (a = a.clone()), (b = b.clone()), () {
+            let a = *a.borrow();
+            if a % 2 == 0 {
+                b.set(pc, a);
+            }
+        });
+        let l2 = lunk::link!((_pc = pc), (_b = b.clone()), (), (b_runs = b_runs.clone()) {
+            b_runs.set(b_runs.get() + 1);
+        });
+        let l3 = lunk::link!((_pc = pc), (_a = a.clone(), _b = b.clone()), (), (ab_runs = ab_runs.clone()) {
+            ab_runs.set(ab_runs.get() + 1);
+        });
+        return (a, b, vec![l1, l2, l3]);
+    }).unwrap();
+    assert_eq!((b_runs.get(), ab_runs.get()), (1, 1));
+
+    // `l1` doesn't write `b`, so `l2` is skipped, but `l3` still runs for `a`
+    eg.event(|pc| a.set(pc, 1));
+    assert_eq!(*b.borrow(), 0);
+    assert_eq!((b_runs.get(), ab_runs.get()), (1, 2));
+
+    // `l1` writes `b`
+    eg.event(|pc| a.set(pc, 2));
+    assert_eq!(*b.borrow(), 2);
+    assert_eq!((b_runs.get(), ab_runs.get()), (2, 3));
+
+    // Setting a `Prim` to the same value still counts as a modification
+    eg.event(|pc| a.set(pc, 2));
+    assert_eq!((b_runs.get(), ab_runs.get()), (3, 4));
+}
+
+#[test]
+fn skip_unmodified_hist_prim() {
+    let eg = lunk::EventGraph::new();
+    let runs = Rc::new(Cell::new(0));
+    let (a, tens, _links) = eg.event(|pc| {
+        let a = lunk::HistPrim::new(pc, 0);
+        let tens = lunk::HistPrim::new(pc, 0);
+        let l1 = lunk::link!((pc = pc), (a = a.clone()), (tens = tens.clone()), () {
+            tens.set(pc, a.get() / 10);
+        });
+        let l2 = lunk::link!((_pc = pc), (_tens = tens.clone()), (), (runs = runs.clone()) {
+            runs.set(runs.get() + 1);
+        });
+        return (a, tens, vec![l1, l2]);
+    }).unwrap();
+    assert_eq!(runs.get(), 1);
+
+    // `tens` is set to an equal value, so `l2` is skipped
+    eg.event(|pc| a.set(pc, 5));
+    assert_eq!(runs.get(), 1);
+
+    // Setting `a` to an equal value skips `l1` too
+    eg.event(|pc| a.set(pc, 5));
+    assert_eq!(runs.get(), 1);
+    eg.event(|pc| a.set(pc, 12));
+    assert_eq!(tens.get(), 1);
+    assert_eq!(runs.get(), 2);
+}
+
+#[test]
+fn animate_prim() {
+    use lunk::EaseExt;
+    let eg = lunk::EventGraph::new();
+    let anim = lunk::Animator::new();
+    let (a, b) = eg.event(|pc| {
+        let a = lunk::Prim::new(0f32);
+        let b = lunk::HistPrim::new(pc, 10f32);
+        return (a, b);
+    }).unwrap();
+    a.set_ease(&anim, 10., 100f32, |x| x);
+    b.set_ease(&anim, 0., 100f32, |x| x);
+    assert!(anim.update(&eg, 50.));
+    assert_eq!(*a.borrow(), 5.);
+    assert_eq!(b.get(), 5.);
+
+    // Replaces the existing animation
+    a.set_ease(&anim, 0., 100f32, |x| x);
+    anim.cancel(&b);
+    assert!(!anim.update(&eg, 100.));
+    assert_eq!(*a.borrow(), 0.);
+    assert_eq!(b.get(), 5.);
+}
+
+#[test]
+fn animate_spring() {
+    use lunk::SpringExt;
+    let eg = lunk::EventGraph::new();
+    let anim = lunk::Animator::new();
+    let a = eg.event(|_pc| {
+        return lunk::Prim::new(0f64);
+    }).unwrap();
+    a.set_spring(&anim, 100., lunk::SpringConfig::default());
+    anim.update(&eg, 50.);
+    let mid = *a.borrow();
+    assert!(mid > 0. && mid < 100.);
+
+    // Retargeting keeps the velocity, so it continues forward briefly
+    a.set_spring(&anim, 0., lunk::SpringConfig::default());
+    anim.update(&eg, 4.);
+    assert!(*a.borrow() > mid);
+    let mut frames = 0;
+    while anim.update(&eg, 16.) {
+        frames += 1;
+        assert!(frames < 1000);
+    }
+    assert_eq!(*a.borrow(), 0.);
+}
+
+#[test]
+fn animate_timeline() {
+    use lunk::{
+        Timeline,
+        Keyframe,
+    };
+    let eg = lunk::EventGraph::new();
+    let anim = lunk::Animator::new();
+    let (a, b, c) = eg.event(|_pc| {
+        return (lunk::Prim::new(0f64), lunk::Prim::new(0f64), lunk::Prim::new(0f64));
+    }).unwrap();
+    let timeline = Timeline::sequence(vec![
+        Timeline::keyframes(&a, 100., vec![Keyframe::new(1., 1., |x| x)]),
+        Timeline::delay(50.),
+        Timeline::parallel(
+            vec![
+                Timeline::keyframes(&b, 100., vec![Keyframe::new(0.5, 10., |x| x), Keyframe::new(1., 0., |x| x)]),
+                Timeline::keyframes(&c, 50., vec![Keyframe::new(1., 5., |x| x * x)])
+            ],
+        )
+    ]);
+    assert_eq!(timeline.duration(), 250.);
+    anim.start(timeline);
+    assert!(anim.update(&eg, 50.));
+    assert_eq!((*a.borrow(), *b.borrow(), *c.borrow()), (0.5, 0., 0.));
+    assert!(anim.update(&eg, 75.));
+    assert_eq!((*a.borrow(), *b.borrow(), *c.borrow()), (1., 0., 0.));
+    assert!(anim.update(&eg, 50.));
+    assert_eq!((*a.borrow(), *b.borrow(), *c.borrow()), (1., 5., 1.25));
+    assert!(anim.update(&eg, 50.));
+    assert_eq!((*a.borrow(), *b.borrow(), *c.borrow()), (1., 5., 5.));
+    assert!(!anim.update(&eg, 50.));
+    assert_eq!((*a.borrow(), *b.borrow(), *c.borrow()), (1., 0., 5.));
+}
+
+#[test]
+fn animate_handle() {
+    use lunk::{
+        EaseExt,
+        AnimationState,
+    };
+    let eg = lunk::EventGraph::new();
+    let anim = lunk::Animator::new();
+    let (opacity, elements) = eg.event(|_pc| {
+        return (lunk::Prim::new(1f64), lunk::List::new(vec!["fading"]));
+    }).unwrap();
+    let fade = opacity.set_ease(&anim, 0., 100f64, |x| x);
+    fade.on_end({
+        let elements = elements.clone();
+        move |pc, state| {
+            assert_eq!(state, AnimationState::Finished);
+            elements.remove(pc, 0);
+        }
+    });
+    let seen = Rc::new(RefCell::new(vec![]));
+    let _link = eg.event(|pc| {
+        return lunk::link!((_pc = pc), (state = fade.state_prim()), (), (seen = seen.clone()) {
+            seen.borrow_mut().push(*state.borrow());
+        });
+    }).unwrap();
+    assert!(anim.update(&eg, 50.));
+    assert_eq!(fade.state(), AnimationState::Running);
+    assert_eq!(elements.borrow_values().len(), 1);
+    assert!(!anim.update(&eg, 60.));
+    assert_eq!(fade.state(), AnimationState::Finished);
+    assert_eq!(elements.borrow_values().len(), 0);
+    assert_eq!(seen.borrow().clone(), vec![AnimationState::Running, AnimationState::Finished]);
+
+    // Replacement and cancellation are visible immediately and delivered on update
+    let first = opacity.set_ease(&anim, 1., 100f64, |x| x);
+    let second = opacity.set_ease(&anim, 0.5, 100f64, |x| x);
+    assert_eq!(first.state(), AnimationState::Replaced);
+    assert_eq!(*first.state_prim().borrow(), AnimationState::Running);
+    anim.cancel(&opacity);
+    assert_eq!(second.state(), AnimationState::Cancelled);
+    assert!(!anim.update(&eg, 10.));
+    assert_eq!(*first.state_prim().borrow(), AnimationState::Replaced);
+    assert_eq!(*second.state_prim().borrow(), AnimationState::Cancelled);
+}
+
+#[test]
+fn easing() {
+    use lunk::{
+        easing,
+        EaseExt,
+    };
+    let penner: Vec<fn(f64) -> f64> = vec![
+        easing::quad_in,
+        easing::cubic_out,
+        easing::quart_in_out,
+        easing::sine_in_out,
+        easing::expo_in,
+        easing::circ_out,
+        easing::back_in_out,
+        easing::elastic_out,
+        easing::bounce_in,
+        easing::bounce_in_out,
+    ];
+    for f in penner {
+        assert!(f(0.).abs() < 1e-9);
+        assert!((f(1.) - 1.).abs() < 1e-9);
+    }
+    assert_eq!(easing::quad_in_out(0.25), 0.125);
+    let line = easing::cubic_bezier(0., 0., 1., 1.);
+    let in_out = easing::cubic_bezier(0.42, 0., 0.58, 1.);
+    for i in 0 ..= 10 {
+        let t = i as f64 / 10.;
+        assert!((line(t) - t).abs() < 1e-6);
+        assert!((in_out(t) + in_out(1. - t) - 1.).abs() < 1e-6);
+    }
+    assert!(in_out(0.25) < 0.25);
+    let steps = easing::steps(4);
+    assert_eq!((steps(0.), steps(0.3), steps(0.99), steps(1.)), (0., 0.25, 0.75, 1.));
+    let start = easing::steps_with(2, easing::StepPosition::Start);
+    assert_eq!((start(0.), start(0.6)), (0.5, 1.));
+    let custom = easing::piecewise_linear(vec![(0., 0.), (0.5, 0.8), (1., 1.)]);
+    assert_eq!((custom(0.25), custom(0.75)), (0.4, 0.9));
+
+    // Closures can be used with `set_ease`
+    let eg = lunk::EventGraph::new();
+    let anim = lunk::Animator::new();
+    let a = eg.event(|_pc| lunk::Prim::new(0f32)).unwrap();
+    a.set_ease(&anim, 8., 100f32, easing::steps(4));
+    anim.update(&eg, 60.);
+    assert_eq!(*a.borrow(), 4.);
+    a.set_ease(&anim, 0., 100f32, easing::cubic_out);
+    anim.update(&eg, 50.);
+    assert_eq!(*a.borrow(), 0.5);
+}
+
 #[test]
 fn cycle() {
     let eg = lunk::EventGraph::new();